@@ -0,0 +1,651 @@
+use crate::{
+    manager::Manager,
+    reminder::{parse_schedule, Reminder, ReminderType, CRON_FIELDS},
+    template,
+};
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use chrono_tz::Etc::UTC;
+use hyper::{
+    body::HttpBody,
+    header::{AUTHORIZATION, CONTENT_TYPE},
+    server::{conn::AddrIncoming, Builder},
+    service::{make_service_fn, service_fn},
+    Body, Method, Request, Response, Server, StatusCode,
+};
+use ring::constant_time;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::json;
+use serenity::model::id::ChannelId;
+use slotmap::{DefaultKey, Key, KeyData};
+use std::{collections::HashMap, convert::Infallible, fmt, net::SocketAddr, sync::Arc};
+
+/// `OpenAPI` description of the API, served at `/openapi.json`
+const SCHEMA: &str = include_str!("openapi.json");
+// Bodies are small JSON objects, anything bigger is refused before it is read
+// into memory
+const MAX_BODY_BYTES: usize = 64 * 1024;
+
+// Failed requests are answered with their status and `{"error": "..."}`
+struct Error(StatusCode, String);
+
+impl Error {
+    fn bad_request(why: impl fmt::Display) -> Self {
+        Self(StatusCode::BAD_REQUEST, why.to_string())
+    }
+
+    fn not_found() -> Self {
+        Self(StatusCode::NOT_FOUND, "not found".to_string())
+    }
+}
+
+type Reply = Result<Response<Body>, Error>;
+
+fn json(status: StatusCode, value: &impl Serialize) -> Response<Body> {
+    Response::builder()
+        .status(status)
+        .header(CONTENT_TYPE, "application/json")
+        // This should never panic, our types always serialize
+        .body(
+            serde_json::to_vec(value)
+                .expect("Error serializing response")
+                .into(),
+        )
+        .expect("Error building API response")
+}
+
+async fn body<T: DeserializeOwned>(req: Request<Body>) -> Result<T, Error> {
+    let mut body = req.into_body();
+    let mut bytes = Vec::new();
+    while let Some(chunk) = body.data().await {
+        let chunk = chunk.map_err(Error::bad_request)?;
+        if bytes.len() + chunk.len() > MAX_BODY_BYTES {
+            return Err(Error(
+                StatusCode::PAYLOAD_TOO_LARGE,
+                format!("body is larger than {MAX_BODY_BYTES} bytes"),
+            ));
+        }
+        bytes.extend_from_slice(&chunk);
+    }
+    serde_json::from_slice(&bytes).map_err(|why| Error::bad_request(format!("invalid body: {why}")))
+}
+
+// Reminder ids are their keys as a number, like in webhook payloads
+fn parse_id(id: &str) -> Result<DefaultKey, Error> {
+    id.parse()
+        .map(|id| KeyData::from_ffi(id).into())
+        .map_err(|_| Error::not_found())
+}
+
+fn parse_cron(fields: &HashMap<String, String>) -> Result<ReminderType, Error> {
+    if let Some(field) = fields.keys().find(|f| !CRON_FIELDS.contains(&f.as_str())) {
+        return Err(Error::bad_request(format!("unknown cron field {field}")));
+    }
+
+    parse_schedule(fields)
+        .map(ReminderType::Scheduled)
+        .map_err(|_| Error::bad_request("invalid cron expression"))
+}
+
+/// A reminder as the API shows it
+#[derive(Serialize)]
+struct ReminderView {
+    id: u64,
+    message: String,
+    /// `cron`, `once` or `rrule`
+    kind: &'static str,
+    /// Cron expression, time or recurrence rule, depending on the kind
+    schedule: String,
+    /// Time it is planned to be sent next
+    next: Option<DateTime<Utc>>,
+    paused: bool,
+    count: Option<u32>,
+    fired: u32,
+}
+
+/// Delay of a one-shot reminder, like `/remindin`
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Delay {
+    #[serde(default)]
+    days: u32,
+    #[serde(default)]
+    hours: u32,
+    #[serde(default)]
+    mins: u32,
+}
+
+/// New reminder, either with the cron fields of `/remindme` or the delay of
+/// `/remindin`
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct NewReminder {
+    message: String,
+    cron: Option<HashMap<String, String>>,
+    #[serde(rename = "in")]
+    delay: Option<Delay>,
+    count: Option<u32>,
+    catch_up: Option<String>,
+}
+
+/// Changes to a reminder, like the buttons of `/menu`
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ReminderChanges {
+    message: Option<String>,
+    cron: Option<HashMap<String, String>>,
+    paused: Option<bool>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TzBody {
+    tz: String,
+}
+
+struct Api {
+    manager: Manager,
+    token: String,
+}
+
+impl Api {
+    fn authorized(&self, req: &Request<Body>) -> bool {
+        let Some(given) = req
+            .headers()
+            .get(AUTHORIZATION)
+            .and_then(|header| header.to_str().ok())
+            .and_then(|header| header.strip_prefix("Bearer "))
+        else {
+            return false;
+        };
+
+        !given.is_empty()
+            && constant_time::verify_slices_are_equal(given.as_bytes(), self.token.as_bytes())
+                .is_ok()
+    }
+
+    async fn route(&self, req: Request<Body>) -> Reply {
+        let path = req.uri().path().to_string();
+        let segments = path
+            .split('/')
+            .filter(|s| !s.is_empty())
+            .collect::<Vec<_>>();
+        let method = req.method().clone();
+
+        if method == Method::GET && segments == ["openapi.json"] {
+            return Ok(Response::builder()
+                .header(CONTENT_TYPE, "application/json")
+                .body(SCHEMA.into())
+                .expect("Error building API response"));
+        }
+        if !self.authorized(&req) {
+            return Err(Error(
+                StatusCode::UNAUTHORIZED,
+                "missing or wrong token".to_string(),
+            ));
+        }
+
+        let ["channels", channel_id, rest @ ..] = segments.as_slice() else {
+            return Err(Error::not_found());
+        };
+        let channel_id = ChannelId(channel_id.parse().map_err(|_| Error::not_found())?);
+        match (method, rest) {
+            (Method::GET, ["tz"]) => Ok(self.tz(channel_id).await),
+            (Method::PUT, ["tz"]) => self.set_tz(channel_id, body(req).await?).await,
+            (Method::GET, ["reminders"]) => Ok(self.list(channel_id).await),
+            (Method::POST, ["reminders"]) => self.create(channel_id, body(req).await?).await,
+            (method, ["reminders", id, action @ ..]) => {
+                let key = parse_id(id)?;
+                match (method, action) {
+                    (Method::GET, []) => self.get(channel_id, key).await,
+                    (Method::PATCH, []) => self.edit(channel_id, key, body(req).await?).await,
+                    (Method::DELETE, []) => self.delete(channel_id, key).await,
+                    (Method::POST, ["skip"]) => self.skip(channel_id, key).await,
+                    _ => Err(Error::not_found()),
+                }
+            }
+            _ => Err(Error::not_found()),
+        }
+    }
+
+    fn view(&self, channel_id: ChannelId, key: DefaultKey, reminder: &Reminder) -> ReminderView {
+        let (kind, schedule) = match &reminder.reminder_type {
+            ReminderType::Scheduled(schedule) => ("cron", schedule.to_string()),
+            ReminderType::Once(at) => ("once", Utc.from_utc_datetime(at).to_rfc3339()),
+            ReminderType::Recurring(rrule) => ("rrule", rrule.to_string()),
+        };
+
+        ReminderView {
+            id: key.data().as_ffi(),
+            message: reminder.msg.clone(),
+            kind,
+            schedule,
+            next: self.manager.planned(channel_id, key),
+            paused: reminder.paused,
+            count: reminder.count,
+            fired: reminder.fired,
+        }
+    }
+
+    async fn reminder(&self, channel_id: ChannelId, key: DefaultKey) -> Result<Reminder, Error> {
+        self.manager
+            .channel_data(channel_id)
+            .await
+            .and_then(|cd| cd.reminders.get(key).cloned())
+            .ok_or_else(Error::not_found)
+    }
+
+    async fn tz(&self, channel_id: ChannelId) -> Response<Body> {
+        let tz = self
+            .manager
+            .channel_data(channel_id)
+            .await
+            .map_or(UTC, |cd| cd.tz);
+
+        json(StatusCode::OK, &TzBody { tz: tz.to_string() })
+    }
+
+    async fn set_tz(&self, channel_id: ChannelId, body: TzBody) -> Reply {
+        self.manager
+            .set_channel_tz(channel_id, &body.tz)
+            .await
+            .map_err(|_| Error::bad_request("unknown timezone"))?;
+
+        Ok(json(StatusCode::OK, &body))
+    }
+
+    async fn list(&self, channel_id: ChannelId) -> Response<Body> {
+        let mut reminders = self
+            .manager
+            .channel_data(channel_id)
+            .await
+            .map(|cd| {
+                cd.reminders
+                    .iter()
+                    .map(|(key, reminder)| self.view(channel_id, key, reminder))
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        // Soonest first like in /menu
+        reminders.sort_by_key(|r| (r.next.is_none(), r.next, r.id));
+
+        json(StatusCode::OK, &reminders)
+    }
+
+    async fn create(&self, channel_id: ChannelId, new: NewReminder) -> Reply {
+        let reminder_type = match (&new.cron, &new.delay) {
+            (Some(cron), None) => parse_cron(cron)?,
+            (None, Some(delay)) => {
+                let mins = i64::from(delay.mins)
+                    + 60 * (i64::from(delay.hours) + 24 * i64::from(delay.days));
+                let at = Utc::now()
+                    .checked_add_signed(Duration::minutes(mins))
+                    .ok_or_else(|| Error::bad_request("delay is too long"))?;
+                ReminderType::Once(at.naive_utc())
+            }
+            _ => return Err(Error::bad_request("give either cron or in")),
+        };
+        if new.message.is_empty() {
+            return Err(Error::bad_request("message cannot be empty"));
+        }
+        template::validate(&new.message).map_err(Error::bad_request)?;
+
+        let mut reminder = Reminder::new(reminder_type, new.message);
+        reminder.count = new.count;
+        reminder.catch_up = match new.catch_up {
+            Some(catch_up) => Some(
+                catch_up
+                    .parse()
+                    .map_err(|()| Error::bad_request("catch_up must be skip, summary or all"))?,
+            ),
+            None => None,
+        };
+        let tz = self.manager.reminder_tz(channel_id, &reminder).await;
+        if reminder.next_after(tz, Utc::now()).is_none() {
+            return Err(Error::bad_request("the reminder would never be sent"));
+        }
+
+        let key = self
+            .manager
+            .add_reminder(channel_id, reminder.clone())
+            .await;
+        Ok(json(
+            StatusCode::CREATED,
+            &self.view(channel_id, key, &reminder),
+        ))
+    }
+
+    async fn get(&self, channel_id: ChannelId, key: DefaultKey) -> Reply {
+        let reminder = self.reminder(channel_id, key).await?;
+
+        Ok(json(StatusCode::OK, &self.view(channel_id, key, &reminder)))
+    }
+
+    async fn edit(
+        &self,
+        channel_id: ChannelId,
+        key: DefaultKey,
+        changes: ReminderChanges,
+    ) -> Reply {
+        let mut reminder = self.reminder(channel_id, key).await?;
+        // Everything is checked before changing anything, then all changes are
+        // stored at once
+        let edited = changes.message.is_some() || changes.cron.is_some();
+        if let Some(paused) = changes.paused {
+            if !reminder.reminder_type.is_repeating() {
+                return Err(Error::bad_request("only repeating reminders can be paused"));
+            }
+            reminder.paused = paused;
+        }
+        if let Some(message) = changes.message {
+            if message.is_empty() {
+                return Err(Error::bad_request("message cannot be empty"));
+            }
+            template::validate(&message).map_err(Error::bad_request)?;
+            reminder.msg = message;
+        }
+        if let Some(cron) = changes.cron {
+            if !matches!(reminder.reminder_type, ReminderType::Scheduled(_)) {
+                return Err(Error::bad_request("only cron reminders have cron fields"));
+            }
+            reminder.reminder_type = parse_cron(&cron)?;
+        }
+
+        // Editing plans the reminder from now, so resuming does not catch up on the
+        // occurrences while it was paused
+        if edited || changes.paused.is_some() {
+            self.manager.edit_reminder(channel_id, key, reminder).await;
+        }
+
+        self.get(channel_id, key).await
+    }
+
+    async fn delete(&self, channel_id: ChannelId, key: DefaultKey) -> Reply {
+        self.reminder(channel_id, key).await?;
+        self.manager.remove_reminder(channel_id, key).await;
+
+        Ok(json(StatusCode::OK, &json!({})))
+    }
+
+    async fn skip(&self, channel_id: ChannelId, key: DefaultKey) -> Reply {
+        self.reminder(channel_id, key).await?;
+        self.manager
+            .skip_reminder(channel_id, key)
+            .await
+            .map_err(Error::bad_request)?;
+
+        self.get(channel_id, key).await
+    }
+}
+
+async fn run(builder: Builder<AddrIncoming>, manager: Manager, token: String) -> hyper::Result<()> {
+    let api = Arc::new(Api { manager, token });
+    let make_service = make_service_fn(move |_| {
+        let api = Arc::clone(&api);
+        async move {
+            Ok::<_, Infallible>(service_fn(move |req| {
+                let api = Arc::clone(&api);
+                async move {
+                    Ok::<_, Infallible>(api.route(req).await.unwrap_or_else(
+                        |Error(status, why)| json(status, &json!({ "error": why })),
+                    ))
+                }
+            }))
+        }
+    });
+
+    builder.serve(make_service).await
+}
+
+/// Serves a JSON API to manage the reminders of any channel, see `openapi.json`.
+/// Every request apart from the schema needs the token as a bearer token
+pub async fn serve(addr: SocketAddr, token: String, manager: Manager) {
+    match Server::try_bind(&addr) {
+        Ok(builder) => {
+            println!("Serving API on {addr}");
+            if let Err(why) = run(builder, manager, token).await {
+                println!("API server error: {why:?}");
+            }
+        }
+        Err(why) => println!("Cannot start API server on {addr}: {why:?}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::TempDb;
+    use reqwest::{Client, StatusCode};
+    use serde_json::Value;
+    use std::net::TcpListener;
+
+    const TOKEN: &str = "token";
+
+    // Starts the API on a free port with a fresh database, returns its URL
+    async fn start(name: &str) -> (String, Manager, TempDb) {
+        let db = TempDb::new(&format!("api-{name}"));
+        let manager = Manager::new(db.open().await);
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(run(
+            Server::from_tcp(listener).unwrap(),
+            manager.clone(),
+            TOKEN.to_string(),
+        ));
+
+        (url, manager, db)
+    }
+
+    #[tokio::test]
+    async fn requires_the_token() {
+        let (url, manager, _db) = start("auth").await;
+        let client = Client::new();
+        let reminders = format!("{url}/channels/1/reminders");
+
+        let status = |req: reqwest::RequestBuilder| async { req.send().await.unwrap().status() };
+        assert_eq!(
+            status(client.get(&reminders)).await,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            status(client.get(&reminders).bearer_auth("wrong")).await,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            status(client.get(&reminders).header(AUTHORIZATION, "Bearer ")).await,
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            status(client.get(&reminders).bearer_auth(TOKEN)).await,
+            StatusCode::OK
+        );
+        // Even with an empty token, requests without one are turned away
+        let api = Api {
+            manager,
+            token: String::new(),
+        };
+        for header in [None, Some(""), Some("Bearer "), Some("Basic ")] {
+            let mut req = Request::builder();
+            if let Some(header) = header {
+                req = req.header(AUTHORIZATION, header);
+            }
+            assert!(!api.authorized(&req.body(Body::empty()).unwrap()));
+        }
+
+        // The schema is public and describes every route
+        let schema: Value = client
+            .get(format!("{url}/openapi.json"))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        for route in [
+            "/channels/{channel_id}/tz",
+            "/channels/{channel_id}/reminders",
+            "/channels/{channel_id}/reminders/{reminder_id}",
+            "/channels/{channel_id}/reminders/{reminder_id}/skip",
+        ] {
+            assert!(schema["paths"].get(route).is_some(), "{route}");
+        }
+    }
+
+    #[tokio::test]
+    async fn manages_reminders() {
+        let (url, manager, _db) = start("reminders").await;
+        let client = Client::new();
+        let channel_id = ChannelId(1);
+        let reminders = format!("{url}/channels/1/reminders");
+
+        let response = client
+            .post(&reminders)
+            .bearer_auth(TOKEN)
+            .json(&json!({ "message": "standup", "cron": { "min": "0", "hour": "9" } }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let created: Value = response.json().await.unwrap();
+        assert_eq!(created["kind"], "cron");
+        let id = created["id"].as_u64().unwrap();
+        let key = KeyData::from_ffi(id).into();
+        // New reminders are planned straight away
+        let next = manager.planned(channel_id, key).unwrap();
+        assert_eq!(created["next"], json!(next));
+
+        let reminder = format!("{reminders}/{id}");
+        let edited: Value = client
+            .patch(&reminder)
+            .bearer_auth(TOKEN)
+            .json(&json!({ "message": "retro", "paused": true }))
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(edited["message"], "retro");
+        assert_eq!(edited["paused"], true);
+        assert_eq!(manager.planned(channel_id, key), None);
+
+        let listed: Value = client
+            .get(&reminders)
+            .bearer_auth(TOKEN)
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(listed, json!([edited]));
+
+        let response = client
+            .delete(&reminder)
+            .bearer_auth(TOKEN)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = client
+            .get(&reminder)
+            .bearer_auth(TOKEN)
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn creates_delayed_reminders() {
+        let (url, manager, _db) = start("delay").await;
+        let client = Client::new();
+        let reminders = format!("{url}/channels/1/reminders");
+        let create = |body: Value| {
+            client
+                .post(&reminders)
+                .bearer_auth(TOKEN)
+                .json(&body)
+                .send()
+        };
+
+        let response = create(json!({ "message": "tea", "in": { "mins": 5 } }))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let created: Value = response.json().await.unwrap();
+        assert_eq!(created["kind"], "once");
+        let key = KeyData::from_ffi(created["id"].as_u64().unwrap()).into();
+        let next = manager.planned(ChannelId(1), key).unwrap();
+        assert!(next > Utc::now() && next - Utc::now() <= Duration::minutes(5));
+
+        // One-shot reminders cannot be paused, and nothing else is changed then
+        let response = client
+            .patch(format!("{reminders}/{}", created["id"]))
+            .bearer_auth(TOKEN)
+            .json(&json!({ "message": "coffee", "paused": true }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let reminder = manager.channel_data(ChannelId(1)).await.unwrap().reminders[key].clone();
+        assert_eq!(reminder.msg, "tea");
+
+        let response =
+            create(json!({ "message": "a".repeat(MAX_BODY_BYTES), "in": { "mins": 5 } }))
+                .await
+                .unwrap();
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+        for body in [
+            json!({ "message": "tea" }),
+            json!({ "message": "tea", "in": {}, "cron": {} }),
+            json!({ "message": "tea", "cron": { "sec": "0" } }),
+            json!({ "message": "{nope}", "in": { "mins": 5 } }),
+            json!({ "message": "tea", "cron": { "year": "2000" } }),
+        ] {
+            let response = create(body.clone()).await.unwrap();
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST, "{body}");
+            let error: Value = response.json().await.unwrap();
+            assert!(error["error"].is_string());
+        }
+    }
+
+    #[tokio::test]
+    async fn sets_the_timezone() {
+        let (url, manager, _db) = start("tz").await;
+        let client = Client::new();
+        let tz = format!("{url}/channels/1/tz");
+
+        let response = client
+            .put(&tz)
+            .bearer_auth(TOKEN)
+            .json(&json!({ "tz": "Europe/Berlin" }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let current: Value = client
+            .get(&tz)
+            .bearer_auth(TOKEN)
+            .send()
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(current, json!({ "tz": "Europe/Berlin" }));
+        assert_eq!(
+            manager.channel_data(ChannelId(1)).await.unwrap().tz,
+            chrono_tz::Europe::Berlin
+        );
+
+        let response = client
+            .put(&tz)
+            .bearer_auth(TOKEN)
+            .json(&json!({ "tz": "Mars/Olympus" }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    }
+}
@@ -0,0 +1,49 @@
+use super::*;
+use serenity::model::Permissions;
+
+pub struct AdminRole;
+
+#[async_trait]
+impl Command for AdminRole {
+    fn name(&self) -> &'static str {
+        "adminrole"
+    }
+
+    fn create(&self, command: &mut CreateApplicationCommand) {
+        command
+            .description("Set a role whose members can change anyone's reminders in this channel")
+            // Whoever picks the admins must already be trusted with the server
+            .default_member_permissions(Permissions::MANAGE_GUILD)
+            .dm_permission(false)
+            .create_option(|option| {
+                option
+                    .name("role")
+                    .description("Reminder admin role, leave empty to remove it")
+                    .kind(CommandOptionType::Role)
+                    .required(false)
+            });
+    }
+
+    async fn handle(
+        &self,
+        ctx: Arc<Context>,
+        manager: &Manager,
+        command: &ApplicationCommandInteraction,
+        options: HashMap<String, CommandDataOptionValue>,
+    ) {
+        let role = match options.get("role") {
+            Some(CommandDataOptionValue::Role(role)) => Some(role.id),
+            _ => None,
+        };
+        manager.set_admin_role(command.channel_id, role).await;
+
+        if let Err(why) = command
+            .create_interaction_response(&ctx.http, move |response| {
+                response.interaction_response_data(|message| message.content("done"))
+            })
+            .await
+        {
+            println!("Cannot respond to slash command: {why:#?}");
+        }
+    }
+}
@@ -0,0 +1,58 @@
+use super::*;
+use crate::reminder::CatchUp as Policy;
+
+pub struct CatchUp;
+
+#[async_trait]
+impl Command for CatchUp {
+    fn name(&self) -> &'static str {
+        "catchup"
+    }
+
+    fn create(&self, command: &mut CreateApplicationCommand) {
+        command
+            .description("Set what happens to reminders missed while the bot was offline")
+            .create_option(|option| {
+                Policy::NAMES.into_iter().fold(
+                    option
+                        .name("policy")
+                        .description(
+                            "Skip them, send one message noting how many were missed or send all",
+                        )
+                        .kind(CommandOptionType::String)
+                        .required(true),
+                    |option, name| option.add_string_choice(name, name),
+                )
+            });
+    }
+
+    async fn handle(
+        &self,
+        ctx: Arc<Context>,
+        manager: &Manager,
+        command: &ApplicationCommandInteraction,
+        options: HashMap<String, CommandDataOptionValue>,
+    ) {
+        let CommandDataOptionValue::String(policy) = &options["policy"] else {
+            panic!("Expected string option");
+        };
+
+        let content = if let Ok(policy) = policy.parse() {
+            manager
+                .set_channel_catch_up(command.channel_id, policy)
+                .await;
+            "done"
+        } else {
+            "invalid policy"
+        };
+
+        if let Err(why) = command
+            .create_interaction_response(&ctx.http, move |response| {
+                response.interaction_response_data(|message| message.content(content))
+            })
+            .await
+        {
+            println!("Cannot respond to slash command: {why:#?}");
+        }
+    }
+}
@@ -11,6 +11,16 @@ pub trait Command {
         ctx: Arc<Context>,
         manager: &Manager,
         command: &ApplicationCommandInteraction,
-        options: HashMap<String, ApplicationCommandInteractionDataOptionValue>,
+        options: HashMap<String, CommandDataOptionValue>,
     );
+
+    /// Suggests values for the option being typed, only commands with
+    /// autocompleted options need to respond
+    async fn autocomplete(
+        &self,
+        _ctx: Arc<Context>,
+        _manager: &Manager,
+        _interaction: &AutocompleteInteraction,
+    ) {
+    }
 }
@@ -0,0 +1,95 @@
+use super::*;
+use crate::reminder_menu::limit_length;
+
+pub struct DeadLetters;
+
+#[async_trait]
+impl Command for DeadLetters {
+    fn name(&self) -> &'static str {
+        "deadletters"
+    }
+
+    fn create(&self, command: &mut CreateApplicationCommand) {
+        command
+            .description("Show reminders that could not be sent in this channel")
+            .create_option(|option| {
+                option
+                    .name("requeue")
+                    .description("Number of the reminder to send again")
+                    .kind(CommandOptionType::Integer)
+                    .required(false)
+            });
+    }
+
+    async fn handle(
+        &self,
+        ctx: Arc<Context>,
+        manager: &Manager,
+        command: &ApplicationCommandInteraction,
+        options: HashMap<String, CommandDataOptionValue>,
+    ) {
+        let content = if let Some(CommandDataOptionValue::Integer(n)) = options.get("requeue") {
+            // Requeued reminders are sent as their author, not as whoever asks
+            let authorized = manager
+                .authorize_admin(command.channel_id, command.member.as_ref())
+                .await;
+            // Dead letters are numbered from 1 in the list
+            let requeued = match (authorized, usize::try_from(*n - 1)) {
+                (Err(why), _) => Err(why),
+                (Ok(()), Ok(index)) => manager
+                    .requeue_dead_letter(command.channel_id, index)
+                    .await
+                    .then_some(())
+                    .ok_or("no such reminder"),
+                (Ok(()), Err(_)) => Err("no such reminder"),
+            };
+
+            match requeued {
+                Ok(()) => "done".to_string(),
+                Err(why) => why.to_string(),
+            }
+        } else {
+            let dead_letters = manager
+                .channel_data(command.channel_id)
+                .await
+                .map(|cd| cd.dead_letters)
+                .unwrap_or_default();
+
+            if dead_letters.is_empty() {
+                "no failed reminders".to_string()
+            } else {
+                let list = dead_letters
+                    .iter()
+                    .enumerate()
+                    .map(|(i, dl)| {
+                        format!(
+                            "{}. <t:{}:f> {} ({})",
+                            i + 1,
+                            dl.failed_at.timestamp(),
+                            limit_length(&dl.msg, 100),
+                            limit_length(&dl.error, 100)
+                        )
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n");
+
+                // Messages are capped at 2000 characters
+                limit_length(&list, 2000)
+            }
+        };
+
+        if let Err(why) = command
+            .create_interaction_response(&ctx.http, move |response| {
+                response.interaction_response_data(|message| {
+                    // Listing failed reminders should not ping who they mention
+                    message
+                        .content(content)
+                        .allowed_mentions(|am| am.empty_parse())
+                })
+            })
+            .await
+        {
+            println!("Cannot respond to slash command: {why:#?}");
+        }
+    }
+}
@@ -0,0 +1,55 @@
+use super::*;
+use crate::reminder::Embed;
+
+// Options of the commands creating reminders that send them as an embed
+pub(super) struct EmbedOptions;
+
+impl EmbedOptions {
+    pub(super) fn create(command: &mut CreateApplicationCommand) {
+        command
+            .create_option(|option| {
+                option
+                    .name("embed")
+                    .description("Send it as an embed rather than plain text")
+                    .kind(CommandOptionType::Boolean)
+                    .required(false)
+            })
+            .create_option(|option| {
+                option
+                    .name("title")
+                    .description("Title of the embed, sends it as one")
+                    .kind(CommandOptionType::String)
+                    .max_length(256)
+                    .required(false)
+            })
+            .create_option(|option| {
+                option
+                    .name("colour")
+                    .description("Colour of the embed in hex, e.g. #ff8800, sends it as one")
+                    .kind(CommandOptionType::String)
+                    .required(false)
+            });
+    }
+
+    pub(super) fn parse(
+        options: &HashMap<String, CommandDataOptionValue>,
+    ) -> Result<Option<Embed>, &'static str> {
+        let get = |name| match options.get(name) {
+            Some(CommandDataOptionValue::String(s)) => Some(s.as_str()),
+            _ => None,
+        };
+        let enabled = matches!(
+            options.get("embed"),
+            Some(CommandDataOptionValue::Boolean(true))
+        );
+
+        let embed = Embed {
+            title: get("title").map(ToString::to_string),
+            colour: get("colour")
+                .map(|colour| Embed::parse_colour(colour).ok_or("invalid colour, use e.g. #ff8800"))
+                .transpose()?,
+        };
+
+        Ok((enabled || embed != Embed::default()).then_some(embed))
+    }
+}
@@ -0,0 +1,77 @@
+use super::*;
+use std::env;
+
+pub struct Feed;
+
+#[async_trait]
+impl Command for Feed {
+    fn name(&self) -> &'static str {
+        "feed"
+    }
+
+    fn create(&self, command: &mut CreateApplicationCommand) {
+        command
+            .description("Creates a calendar feed URL for this channel's reminders")
+            .create_option(|option| {
+                option
+                    .name("revoke")
+                    .description("Stops serving the feed, a new URL can be created afterwards")
+                    .kind(CommandOptionType::Boolean)
+                    .required(false)
+            });
+    }
+
+    async fn handle(
+        &self,
+        ctx: Arc<Context>,
+        manager: &Manager,
+        command: &ApplicationCommandInteraction,
+        options: HashMap<String, CommandDataOptionValue>,
+    ) {
+        // Rotating or revoking the URL breaks everyone's subscriptions
+        let authorized = manager
+            .authorize_admin(command.channel_id, command.member.as_ref())
+            .await;
+        let revoke = matches!(
+            options.get("revoke"),
+            Some(CommandDataOptionValue::Boolean(true))
+        );
+        let base = env::var("FEED_URL")
+            .ok()
+            .filter(|url| !url.trim().is_empty());
+        let content = match (authorized, revoke, base) {
+            (Err(why), ..) => why.to_string(),
+            (Ok(()), true, _) => {
+                manager.set_feed_token(command.channel_id, None).await;
+                "done".to_string()
+            }
+            (Ok(()), false, _) if env::var("FEED_ADDR").is_err() => {
+                "calendar feeds are not served by this bot".to_string()
+            }
+            (Ok(()), false, None) => {
+                "calendar feeds have no public URL, set FEED_URL to hand them out".to_string()
+            }
+            (Ok(()), false, Some(base)) => {
+                // Creating a feed again replaces the old URL, so a leaked one can be
+                // rotated
+                let token = secret();
+                manager
+                    .set_feed_token(command.channel_id, Some(token.clone()))
+                    .await;
+
+                format!("Subscribe to {}/{token}.ics", base.trim_end_matches('/'))
+            }
+        };
+
+        // The URL gives access to the channel's reminders, so only the caller sees it
+        if let Err(why) = command
+            .create_interaction_response(&ctx.http, move |response| {
+                response
+                    .interaction_response_data(|message| message.content(content).ephemeral(true))
+            })
+            .await
+        {
+            println!("Cannot respond to slash command: {why:#?}");
+        }
+    }
+}
@@ -0,0 +1,147 @@
+use super::*;
+use crate::{ical, reminder_menu::limit_length};
+use chrono::Utc;
+use serenity::model::{application::interaction::InteractionResponseType, channel::AttachmentType};
+
+// Calendars larger than this are not downloaded
+const MAX_IMPORT_SIZE: u64 = 1024 * 1024;
+
+pub struct Export;
+
+#[async_trait]
+impl Command for Export {
+    fn name(&self) -> &'static str {
+        "export"
+    }
+
+    fn create(&self, command: &mut CreateApplicationCommand) {
+        command.description("Exports this channel's reminders as an iCalendar (.ics) file");
+    }
+
+    async fn handle(
+        &self,
+        ctx: Arc<Context>,
+        manager: &Manager,
+        command: &ApplicationCommandInteraction,
+        _options: HashMap<String, CommandDataOptionValue>,
+    ) {
+        let count = manager
+            .channel_data(command.channel_id)
+            .await
+            .map_or(0, |cd| cd.reminders.len());
+        let ics = manager
+            .export(command.channel_id)
+            .await
+            .filter(|_| count > 0);
+
+        if let Err(why) = command
+            .create_interaction_response(&ctx.http, move |response| {
+                response.interaction_response_data(|message| match ics {
+                    Some(ics) => message.content(format!("{count} reminders")).add_file(
+                        AttachmentType::Bytes {
+                            data: ics.into_bytes().into(),
+                            filename: "reminders.ics".to_string(),
+                        },
+                    ),
+                    None => message.content("no reminders"),
+                })
+            })
+            .await
+        {
+            println!("Cannot respond to slash command: {why:#?}");
+        }
+    }
+}
+
+pub struct Import;
+
+impl Import {
+    async fn import(
+        manager: &Manager,
+        command: &ApplicationCommandInteraction,
+        options: &HashMap<String, CommandDataOptionValue>,
+    ) -> String {
+        // The file option is required, we are guaranteed to have it
+        let Some(CommandDataOptionValue::Attachment(file)) = options.get("file") else {
+            panic!("Expected file to be an attachment");
+        };
+        if file.size > MAX_IMPORT_SIZE {
+            return "the file is too large".to_string();
+        }
+        let ics = match file.download().await.map(String::from_utf8) {
+            Ok(Ok(ics)) => ics,
+            Ok(Err(_)) => return "the file is not a calendar".to_string(),
+            Err(why) => {
+                println!("Cannot download calendar: {why:#?}");
+                return "cannot download the file".to_string();
+            }
+        };
+
+        let tz = manager.user_tz(command.user.id, command.channel_id).await;
+        let (reminders, skipped) = ical::import(&ics, tz, Utc::now());
+        let imported = reminders.len();
+        for mut reminder in reminders {
+            reminder.author = Some(command.user.id);
+            manager.add_reminder(command.channel_id, reminder).await;
+        }
+
+        let content = if skipped.is_empty() {
+            format!("imported {imported} reminders")
+        } else {
+            format!(
+                "imported {imported} reminders, skipped {}:\n{}",
+                skipped.len(),
+                skipped.join("\n")
+            )
+        };
+        // Messages are capped at 2000 characters
+        limit_length(&content, 2000)
+    }
+}
+
+#[async_trait]
+impl Command for Import {
+    fn name(&self) -> &'static str {
+        "import"
+    }
+
+    fn create(&self, command: &mut CreateApplicationCommand) {
+        command
+            .description("Creates reminders from the events in an iCalendar (.ics) file")
+            .create_option(|option| {
+                option
+                    .name("file")
+                    .description("Calendar file, events fire at their first alarm or start")
+                    .kind(CommandOptionType::Attachment)
+                    .required(true)
+            });
+    }
+
+    async fn handle(
+        &self,
+        ctx: Arc<Context>,
+        manager: &Manager,
+        command: &ApplicationCommandInteraction,
+        options: HashMap<String, CommandDataOptionValue>,
+    ) {
+        // Downloading the file may take longer than Discord waits for a response
+        if let Err(why) = command
+            .create_interaction_response(&ctx.http, |response| {
+                response.kind(InteractionResponseType::DeferredChannelMessageWithSource)
+            })
+            .await
+        {
+            println!("Cannot respond to slash command: {why:#?}");
+            return;
+        }
+
+        let content = Self::import(manager, command, &options).await;
+
+        if let Err(why) = command
+            .edit_original_interaction_response(&ctx.http, |response| response.content(content))
+            .await
+        {
+            println!("Cannot respond to slash command: {why:#?}");
+        }
+    }
+}
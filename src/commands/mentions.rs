@@ -0,0 +1,38 @@
+use super::*;
+use crate::reminder::Mentions;
+
+// Options shared by the commands creating reminders, picking who gets pinged
+// when the reminder is sent
+pub(super) struct MentionOptions;
+
+impl MentionOptions {
+    pub(super) fn create(command: &mut CreateApplicationCommand) {
+        command
+            .create_option(|option| {
+                option
+                    .name("user")
+                    .description("User to ping when it is sent")
+                    .kind(CommandOptionType::User)
+                    .required(false)
+            })
+            .create_option(|option| {
+                option
+                    .name("role")
+                    .description("Role to ping when it is sent")
+                    .kind(CommandOptionType::Role)
+                    .required(false)
+            });
+    }
+
+    pub(super) fn parse(options: &HashMap<String, CommandDataOptionValue>) -> Mentions {
+        let mut mentions = Mentions::default();
+        if let Some(CommandDataOptionValue::User(user, _)) = options.get("user") {
+            mentions.users.push(user.id);
+        }
+        if let Some(CommandDataOptionValue::Role(role)) = options.get("role") {
+            mentions.roles.push(role.id);
+        }
+
+        mentions
+    }
+}
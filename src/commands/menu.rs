@@ -5,7 +5,7 @@ pub struct Menu;
 
 #[async_trait]
 impl Command for Menu {
-    fn name(&self) -> &str {
+    fn name(&self) -> &'static str {
         "menu"
     }
 
@@ -18,16 +18,16 @@ impl Command for Menu {
         ctx: Arc<Context>,
         manager: &Manager,
         command: &ApplicationCommandInteraction,
-        _options: HashMap<String, ApplicationCommandInteractionDataOptionValue>,
+        _options: HashMap<String, CommandDataOptionValue>,
     ) {
-        let menu = ReminderMenu::new(&manager, command.channel_id).await;
+        let menu = ReminderMenu::new(manager, command.channel_id, command.user.id).await;
         if let Err(why) = command
             .create_interaction_response(&ctx.http, move |response| {
                 response.interaction_response_data(|command| menu.create(command))
             })
             .await
         {
-            println!("Cannot respond to slash command: {:#?}", why);
+            println!("Cannot respond to slash command: {why:#?}");
         }
     }
 }
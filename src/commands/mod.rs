@@ -1,23 +1,69 @@
 use crate::manager::Manager;
+use embed::EmbedOptions;
+use mentions::MentionOptions;
+use nag::NagOptions;
+use rand::{distributions::Alphanumeric, Rng};
 use serenity::{
     async_trait,
     builder::CreateApplicationCommand,
-    model::interactions::application_command::{
-        ApplicationCommandInteraction, ApplicationCommandInteractionDataOptionValue,
-        ApplicationCommandOptionType,
+    model::application::{
+        command::CommandOptionType,
+        interaction::{
+            application_command::{ApplicationCommandInteraction, CommandDataOptionValue},
+            autocomplete::AutocompleteInteraction,
+        },
     },
     prelude::*,
 };
 use std::{collections::HashMap, sync::Arc};
+use target::TargetOptions;
+use webhook::WebhookOptions;
 
+mod adminrole;
+mod catchup;
 mod command;
+mod deadletters;
+mod embed;
+mod feed;
+mod ical;
+mod mentions;
 mod menu;
+mod mytz;
+mod nag;
+mod pause;
 mod remind;
 mod remindin;
+mod remindrule;
+mod remindwhen;
+mod snooze;
+mod target;
 mod tz;
+mod webhook;
 
+pub use adminrole::AdminRole;
+pub use catchup::CatchUp;
 pub use command::Command;
+pub use deadletters::DeadLetters;
+pub use feed::Feed;
+pub use ical::{Export, Import};
 pub use menu::Menu;
+pub use mytz::MyTz;
+pub use pause::{Pause, Resume, Skip};
 pub use remind::{RemindMe, RemindOnce};
 pub use remindin::RemindIn;
+pub use remindrule::RemindRule;
+pub use remindwhen::RemindWhen;
+pub use snooze::Snooze;
 pub use tz::Tz;
+
+// Long enough that feed URLs and webhook secrets cannot be guessed
+const SECRET_LENGTH: usize = 32;
+
+// Generates a random token to hand out to users
+fn secret() -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(SECRET_LENGTH)
+        .map(char::from)
+        .collect()
+}
@@ -0,0 +1,52 @@
+use super::*;
+
+pub struct MyTz;
+
+#[async_trait]
+impl Command for MyTz {
+    fn name(&self) -> &'static str {
+        "mytz"
+    }
+
+    fn create(&self, command: &mut CreateApplicationCommand) {
+        command
+            .description("Set your own timezone, used instead of the channel's for your reminders")
+            .create_option(|option| {
+                option
+                    .name("tz")
+                    .description("IANA timezone name, leave empty to use the channel's timezone")
+                    .kind(CommandOptionType::String)
+                    .required(false)
+            });
+    }
+
+    async fn handle(
+        &self,
+        ctx: Arc<Context>,
+        manager: &Manager,
+        command: &ApplicationCommandInteraction,
+        options: HashMap<String, CommandDataOptionValue>,
+    ) {
+        let tz_str = match options.get("tz") {
+            Some(CommandDataOptionValue::String(tz_str)) => Some(tz_str.as_str()),
+            _ => None,
+        };
+
+        let content = if manager.set_user_tz(command.user.id, tz_str).await.is_ok() {
+            "done"
+        } else {
+            "invalid timezone (list of timezone names: <https://w.wiki/4Jx>, capitalization matters!)"
+        }
+        .to_string();
+
+        if let Err(why) = command
+            .create_interaction_response(&ctx.http, move |response| {
+                response
+                    .interaction_response_data(|message| message.content(content).ephemeral(true))
+            })
+            .await
+        {
+            println!("Cannot respond to slash command: {why:#?}");
+        }
+    }
+}
@@ -0,0 +1,37 @@
+use super::*;
+use crate::reminder::Nag;
+
+// Options shared by the commands creating reminders, making the reminder repeat
+// until someone presses its Done button
+pub(super) struct NagOptions;
+
+impl NagOptions {
+    pub(super) fn create(command: &mut CreateApplicationCommand) {
+        command
+            .create_option(|option| {
+                option
+                    .name("nag")
+                    .description("Send it again every this many minutes until someone presses Done")
+                    .kind(CommandOptionType::Integer)
+                    .min_int_value(1)
+                    .required(false)
+            })
+            .create_option(|option| {
+                option
+                    .name("attempts")
+                    .description("Times to send it before giving up when nagging")
+                    .kind(CommandOptionType::Integer)
+                    .min_int_value(1)
+                    .required(false)
+            });
+    }
+
+    pub(super) fn parse(options: &HashMap<String, CommandDataOptionValue>) -> Option<Nag> {
+        let get = |name| match options.get(name) {
+            Some(CommandDataOptionValue::Integer(i)) => u32::try_from(*i).ok(),
+            _ => None,
+        };
+
+        get("nag").map(|every| Nag::new(every, get("attempts")))
+    }
+}
@@ -0,0 +1,212 @@
+use super::*;
+use crate::{reminder::Reminder, reminder_menu::limit_length};
+use slotmap::DefaultKey;
+
+// Shared implementation of the commands acting on a single repeating reminder,
+// which is picked by autocompleting its message
+struct ReminderAction;
+
+impl ReminderAction {
+    fn create(command: &mut CreateApplicationCommand, description: &str) {
+        command.description(description).create_option(|option| {
+            option
+                .name("reminder")
+                .description("Repeating reminder, search by message")
+                .kind(CommandOptionType::String)
+                .set_autocomplete(true)
+                .required(true)
+        });
+    }
+
+    async fn autocomplete(
+        ctx: Arc<Context>,
+        manager: &Manager,
+        interaction: &AutocompleteInteraction,
+        filter: impl Fn(&Reminder) -> bool + Send,
+    ) {
+        let typed = interaction
+            .data
+            .options
+            .iter()
+            .find(|o| o.focused)
+            .and_then(|o| o.value.as_ref())
+            .and_then(|v| v.as_str())
+            .unwrap_or_default()
+            .to_lowercase();
+        let reminders = manager
+            .channel_data(interaction.channel_id)
+            .await
+            .map(|cd| cd.reminders)
+            .unwrap_or_default();
+
+        if let Err(why) = interaction
+            .create_autocomplete_response(&ctx.http, |response| {
+                reminders
+                    .iter()
+                    .filter(|(_, r)| r.reminder_type.is_repeating())
+                    .filter(|(_, r)| filter(r) && r.msg.to_lowercase().contains(&typed))
+                    // Discord shows at most 25 choices
+                    .take(25)
+                    .fold(response, |response, (k, r)| {
+                        // This should never panic, any key should be stringifiable
+                        let key = serde_json::to_string(&k).expect("Error serializing key");
+                        response.add_string_choice(limit_length(&r.msg, 100), key)
+                    })
+            })
+            .await
+        {
+            println!("Cannot respond to autocomplete: {why:#?}");
+        }
+    }
+
+    // The picked reminder, if the user is allowed to change it
+    async fn key(
+        manager: &Manager,
+        command: &ApplicationCommandInteraction,
+        options: &HashMap<String, CommandDataOptionValue>,
+    ) -> Result<DefaultKey, &'static str> {
+        let key = match options.get("reminder") {
+            Some(CommandDataOptionValue::String(s)) => serde_json::from_str(s).ok(),
+            _ => None,
+        }
+        .ok_or("invalid reminder")?;
+        manager
+            .authorize(
+                command.channel_id,
+                key,
+                command.user.id,
+                command.member.as_ref(),
+            )
+            .await?;
+
+        Ok(key)
+    }
+
+    async fn respond(
+        ctx: Arc<Context>,
+        command: &ApplicationCommandInteraction,
+        result: Result<(), &'static str>,
+    ) {
+        // Only the user who failed needs to see why
+        let (content, ephemeral) = result.map_or_else(|why| (why, true), |()| ("done", false));
+
+        if let Err(why) = command
+            .create_interaction_response(&ctx.http, move |response| {
+                response.interaction_response_data(|message| {
+                    message.content(content).ephemeral(ephemeral)
+                })
+            })
+            .await
+        {
+            println!("Cannot respond to slash command: {why:#?}");
+        }
+    }
+}
+
+pub struct Pause;
+
+#[async_trait]
+impl Command for Pause {
+    fn name(&self) -> &'static str {
+        "pause"
+    }
+
+    fn create(&self, command: &mut CreateApplicationCommand) {
+        ReminderAction::create(command, "Stops a repeating reminder until it is resumed");
+    }
+
+    async fn handle(
+        &self,
+        ctx: Arc<Context>,
+        manager: &Manager,
+        command: &ApplicationCommandInteraction,
+        options: HashMap<String, CommandDataOptionValue>,
+    ) {
+        let result = match ReminderAction::key(manager, command, &options).await {
+            Ok(key) => manager.pause_reminder(command.channel_id, key).await,
+            Err(why) => Err(why),
+        };
+        ReminderAction::respond(ctx, command, result).await;
+    }
+
+    async fn autocomplete(
+        &self,
+        ctx: Arc<Context>,
+        manager: &Manager,
+        interaction: &AutocompleteInteraction,
+    ) {
+        ReminderAction::autocomplete(ctx, manager, interaction, |r| !r.paused).await;
+    }
+}
+
+pub struct Resume;
+
+#[async_trait]
+impl Command for Resume {
+    fn name(&self) -> &'static str {
+        "resume"
+    }
+
+    fn create(&self, command: &mut CreateApplicationCommand) {
+        ReminderAction::create(command, "Lets a paused reminder be sent again");
+    }
+
+    async fn handle(
+        &self,
+        ctx: Arc<Context>,
+        manager: &Manager,
+        command: &ApplicationCommandInteraction,
+        options: HashMap<String, CommandDataOptionValue>,
+    ) {
+        let result = match ReminderAction::key(manager, command, &options).await {
+            Ok(key) => manager.resume_reminder(command.channel_id, key).await,
+            Err(why) => Err(why),
+        };
+        ReminderAction::respond(ctx, command, result).await;
+    }
+
+    async fn autocomplete(
+        &self,
+        ctx: Arc<Context>,
+        manager: &Manager,
+        interaction: &AutocompleteInteraction,
+    ) {
+        ReminderAction::autocomplete(ctx, manager, interaction, |r| r.paused).await;
+    }
+}
+
+pub struct Skip;
+
+#[async_trait]
+impl Command for Skip {
+    fn name(&self) -> &'static str {
+        "skip"
+    }
+
+    fn create(&self, command: &mut CreateApplicationCommand) {
+        ReminderAction::create(command, "Skips the next time a repeating reminder is sent");
+    }
+
+    async fn handle(
+        &self,
+        ctx: Arc<Context>,
+        manager: &Manager,
+        command: &ApplicationCommandInteraction,
+        options: HashMap<String, CommandDataOptionValue>,
+    ) {
+        let result = match ReminderAction::key(manager, command, &options).await {
+            Ok(key) => manager.skip_reminder(command.channel_id, key).await,
+            Err(why) => Err(why),
+        };
+        ReminderAction::respond(ctx, command, result).await;
+    }
+
+    async fn autocomplete(
+        &self,
+        ctx: Arc<Context>,
+        manager: &Manager,
+        interaction: &AutocompleteInteraction,
+    ) {
+        ReminderAction::autocomplete(ctx, manager, interaction, |r| !r.paused).await;
+    }
+}
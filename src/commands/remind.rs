@@ -1,8 +1,11 @@
 use super::*;
-use crate::reminder::{Reminder, ReminderType};
-use chrono_tz::Etc::UTC;
-use cron::Schedule;
-use std::str::FromStr;
+use crate::{
+    reminder::{parse_schedule, CatchUp, Embed, Reminder, ReminderType, Target, Webhook},
+    sink::SIGNATURE_HEADER,
+    template, time_parser,
+};
+use chrono::Utc;
+use chrono_tz::Tz;
 
 struct Remind;
 
@@ -14,144 +17,167 @@ impl Remind {
                 option
                     .name("msg")
                     .description("Message to be sent")
-                    .kind(ApplicationCommandOptionType::String)
+                    .kind(CommandOptionType::String)
                     .required(true)
             })
             .create_option(|option| {
                 option
                     .name("min")
                     .description("Minute (0-59)")
-                    .kind(ApplicationCommandOptionType::String)
+                    .kind(CommandOptionType::String)
                     .required(false)
             })
             .create_option(|option| {
                 option
                     .name("hour")
                     .description("Hour (0-23)")
-                    .kind(ApplicationCommandOptionType::String)
+                    .kind(CommandOptionType::String)
                     .required(false)
             })
             .create_option(|option| {
                 option
                     .name("dom")
                     .description("Day of month (1-31)")
-                    .kind(ApplicationCommandOptionType::String)
+                    .kind(CommandOptionType::String)
                     .required(false)
             })
             .create_option(|option| {
                 option
                     .name("month")
                     .description("Month (1-12 or Jan-Dec)")
-                    .kind(ApplicationCommandOptionType::String)
+                    .kind(CommandOptionType::String)
                     .required(false)
             })
             .create_option(|option| {
                 option
                     .name("dow")
                     .description("Day of week (Sun-Sat)")
-                    .kind(ApplicationCommandOptionType::String)
+                    .kind(CommandOptionType::String)
                     .required(false)
             })
             .create_option(|option| {
                 option
                     .name("year")
                     .description("Year")
-                    .kind(ApplicationCommandOptionType::String)
+                    .kind(CommandOptionType::String)
                     .required(false)
             });
+        MentionOptions::create(command);
+        NagOptions::create(command);
+    }
+
+    // Builds the reminder described by the string options
+    fn reminder(
+        options: &HashMap<String, String>,
+        command: &ApplicationCommandInteraction,
+        tz: Tz,
+    ) -> Result<Reminder, &'static str> {
+        let sched = parse_schedule(options).map_err(|_| "invalid cron expression")?;
+        // The msg option is required, we are guaranteed to have it
+        let msg = options.get("msg").unwrap().clone();
+
+        if command.data.name == "remindonce" {
+            let datetime = sched
+                .upcoming(tz)
+                .next()
+                .ok_or("schedule has no upcoming times")?;
+            return Ok(Reminder::new(ReminderType::Once(datetime.naive_utc()), msg));
+        }
+
+        let mut reminder = Reminder::new(ReminderType::Scheduled(sched), msg);
+        reminder.catch_up = options.get("catchup").and_then(|c| c.parse().ok());
+        let parse = |name| {
+            options
+                .get(name)
+                .map(|when| time_parser::parse(when, tz, Utc::now()))
+                .transpose()
+        };
+        reminder.starts_at = parse("starts")?;
+        reminder.ends_at = parse("ends")?;
+
+        Ok(reminder)
+    }
+
+    // Parses the options only /remindme has
+    async fn extras(
+        ctx: &Context,
+        command: &ApplicationCommandInteraction,
+        options: &HashMap<String, CommandDataOptionValue>,
+    ) -> Result<(Option<Embed>, Option<Target>, Option<Webhook>), &'static str> {
+        Ok((
+            EmbedOptions::parse(options)?,
+            TargetOptions::parse(ctx, command, options).await?,
+            WebhookOptions::parse(command, options).await?,
+        ))
     }
 
     async fn handle(
         ctx: Arc<Context>,
         manager: &Manager,
         command: &ApplicationCommandInteraction,
-        options: HashMap<String, ApplicationCommandInteractionDataOptionValue>,
+        options: HashMap<String, CommandDataOptionValue>,
     ) {
+        let mentions = MentionOptions::parse(&options);
+        let nag = NagOptions::parse(&options);
+        let extras = Self::extras(&ctx, command, &options).await;
+        let count = match options.get("count") {
+            Some(CommandDataOptionValue::Integer(count)) => u32::try_from(*count).ok(),
+            _ => None,
+        };
         let options = options
             .into_iter()
-            .map(|(k, v)| {
-                if let ApplicationCommandInteractionDataOptionValue::String(s) = v {
-                    (k, s.clone())
+            .filter_map(|(k, v)| {
+                if let CommandDataOptionValue::String(s) = v {
+                    Some((k, s))
                 } else {
-                    panic!("Expected string option");
+                    None
                 }
             })
             .collect::<HashMap<_, _>>();
+        let tz = manager.user_tz(command.user.id, command.channel_id).await;
 
-        // cron string construction
-
-        // Day of month and day of week are interrelated, therefore we must be careful
-        // when using them as fallback values
-        //
-        // If only one is set, we should set the other to "?"
-        let question_mark = "?".to_string();
-        let asterisk = "*".to_string();
-        let (dom, dow) = {
-            let dom_opt = options.get("dom");
-            let dow_opt = options.get("dow");
-
-            if let Some(dom) = dom_opt {
-                if let Some(dow) = dow_opt {
-                    (dom, dow)
+        let reminder = extras.map_err(ToString::to_string).and_then(|extras| {
+            let reminder = Self::reminder(&options, command, tz)?;
+            template::validate(&reminder.msg)?;
+            Ok((reminder, extras))
+        });
+        // Webhook secrets are only shown to whoever created the reminder
+        let mut secret = None;
+        let content = match reminder {
+            Ok((mut reminder, (embed, target, webhook))) => {
+                reminder.count = count;
+                reminder.author = Some(command.user.id);
+                reminder.mentions = mentions;
+                reminder.nag = nag;
+                reminder.embed = embed;
+                reminder.target = target;
+                secret = webhook.as_ref().map(|webhook| webhook.secret.clone());
+                reminder.webhook = webhook;
+                if reminder.next_after(tz, Utc::now()).is_some() {
+                    manager.add_reminder(command.channel_id, reminder).await;
+                    match &secret {
+                        Some(secret) => format!(
+                            "done, webhook requests are signed with `{secret}` in their \
+                             {SIGNATURE_HEADER} header"
+                        ),
+                        None => "done".to_string(),
+                    }
                 } else {
-                    (dom, &question_mark)
+                    "the reminder would never be sent".to_string()
                 }
-            } else if let Some(dow) = dow_opt {
-                (&question_mark, dow)
-            } else {
-                (&asterisk, &asterisk)
             }
+            Err(why) => why,
         };
 
-        // We always put a 0 in the seconds slot since it is unlikely to be useful to
-        // the end user
-        let sched = format!(
-            "0 {} {} {} {} {} {}",
-            options.get("min").unwrap_or(&asterisk),
-            options.get("hour").unwrap_or(&asterisk),
-            dom,
-            options.get("month").unwrap_or(&asterisk),
-            dow,
-            options.get("year").unwrap_or(&asterisk),
-        );
-
-        let content = if let Ok(sched) = Schedule::from_str(&sched) {
-            // The msg option is required, we are guaranteed to have it
-            let msg = options.get("msg").unwrap().to_string();
-
-            let reminder_type = if command.data.name == "remindonce" {
-                ReminderType::Once(
-                    sched
-                        .upcoming(manager.channel_tz(command.channel_id).await.unwrap_or(UTC))
-                        .next()
-                        .expect("Invalid schedule")
-                        .naive_utc(),
-                )
-            } else {
-                ReminderType::Scheduled(sched)
-            };
-
-            manager
-                .add_reminder(
-                    Arc::clone(&ctx),
-                    command.channel_id,
-                    Reminder { reminder_type, msg },
-                )
-                .await;
-
-            "done"
-        } else {
-            "invalid cron expression"
-        }
-        .to_string();
-
         if let Err(why) = command
             .create_interaction_response(&ctx.http, move |response| {
-                response.interaction_response_data(|message| message.content(content))
+                response.interaction_response_data(|message| {
+                    message.content(content).ephemeral(secret.is_some())
+                })
             })
             .await
         {
-            println!("Cannot respond to slash command: {:#?}", why);
+            println!("Cannot respond to slash command: {why:#?}");
         }
     }
 }
@@ -160,7 +186,7 @@ pub struct RemindMe;
 
 #[async_trait]
 impl Command for RemindMe {
-    fn name(&self) -> &str {
+    fn name(&self) -> &'static str {
         "remindme"
     }
 
@@ -169,6 +195,42 @@ impl Command for RemindMe {
             command,
             "Sends message at scheduled time(s) using cron format",
         );
+        command
+            .create_option(|option| {
+                CatchUp::NAMES.into_iter().fold(
+                    option
+                        .name("catchup")
+                        .description("What to do with times missed while offline")
+                        .kind(CommandOptionType::String)
+                        .required(false),
+                    |option, name| option.add_string_choice(name, name),
+                )
+            })
+            .create_option(|option| {
+                option
+                    .name("count")
+                    .description("Number of times to send it before it is removed")
+                    .kind(CommandOptionType::Integer)
+                    .min_int_value(1)
+                    .required(false)
+            })
+            .create_option(|option| {
+                option
+                    .name("starts")
+                    .description("Do not send it before this time, e.g. dec 1 9am")
+                    .kind(CommandOptionType::String)
+                    .required(false)
+            })
+            .create_option(|option| {
+                option
+                    .name("ends")
+                    .description("Do not send it after this time, e.g. 2026-12-31 18:00")
+                    .kind(CommandOptionType::String)
+                    .required(false)
+            });
+        EmbedOptions::create(command);
+        TargetOptions::create(command);
+        WebhookOptions::create(command);
     }
 
     async fn handle(
@@ -176,7 +238,7 @@ impl Command for RemindMe {
         ctx: Arc<Context>,
         manager: &Manager,
         command: &ApplicationCommandInteraction,
-        options: HashMap<String, ApplicationCommandInteractionDataOptionValue>,
+        options: HashMap<String, CommandDataOptionValue>,
     ) {
         Remind::handle(ctx, manager, command, options).await;
     }
@@ -202,7 +264,7 @@ impl Command for RemindOnce {
         ctx: Arc<Context>,
         manager: &Manager,
         command: &ApplicationCommandInteraction,
-        options: HashMap<String, ApplicationCommandInteractionDataOptionValue>,
+        options: HashMap<String, CommandDataOptionValue>,
     ) {
         Remind::handle(ctx, manager, command, options).await;
     }
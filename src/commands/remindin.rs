@@ -1,12 +1,15 @@
 use super::*;
-use crate::reminder::{Reminder, ReminderType};
+use crate::{
+    reminder::{Reminder, ReminderType},
+    template,
+};
 use chrono::{offset::Utc, Duration};
 
 pub struct RemindIn;
 
 #[async_trait]
 impl Command for RemindIn {
-    fn name(&self) -> &str {
+    fn name(&self) -> &'static str {
         "remindin"
     }
 
@@ -17,30 +20,32 @@ impl Command for RemindIn {
                 option
                     .name("msg")
                     .description("Message to be sent")
-                    .kind(ApplicationCommandOptionType::String)
+                    .kind(CommandOptionType::String)
                     .required(true)
             })
             .create_option(|option| {
                 option
                     .name("mins")
                     .description("Minutes")
-                    .kind(ApplicationCommandOptionType::Integer)
+                    .kind(CommandOptionType::Integer)
                     .required(false)
             })
             .create_option(|option| {
                 option
                     .name("hours")
                     .description("Hours")
-                    .kind(ApplicationCommandOptionType::Integer)
+                    .kind(CommandOptionType::Integer)
                     .required(false)
             })
             .create_option(|option| {
                 option
                     .name("days")
                     .description("Days")
-                    .kind(ApplicationCommandOptionType::Integer)
+                    .kind(CommandOptionType::Integer)
                     .required(false)
             });
+        MentionOptions::create(command);
+        NagOptions::create(command);
     }
 
     async fn handle(
@@ -48,20 +53,20 @@ impl Command for RemindIn {
         ctx: Arc<Context>,
         manager: &Manager,
         command: &ApplicationCommandInteraction,
-        options: HashMap<String, ApplicationCommandInteractionDataOptionValue>,
+        options: HashMap<String, CommandDataOptionValue>,
     ) {
-        let msg = if let ApplicationCommandInteractionDataOptionValue::String(s) =
-            options.get("msg").unwrap()
-        {
+        let msg = if let CommandDataOptionValue::String(s) = options.get("msg").unwrap() {
             s
         } else {
             panic!("Expected message to be string")
         }
-        .to_string();
+        .clone();
+        let mentions = MentionOptions::parse(&options);
+        let nag = NagOptions::parse(&options);
         let options = options
             .into_iter()
             .filter_map(|(k, v)| {
-                if let ApplicationCommandInteractionDataOptionValue::Integer(i) = v {
+                if let CommandDataOptionValue::Integer(i) = v {
                     Some((k, i))
                 } else {
                     None
@@ -75,24 +80,30 @@ impl Command for RemindIn {
         let delay = Duration::minutes(get("mins") + 60 * (get("hours") + 24 * get("days")));
         let later = Utc::now() + delay;
 
-        manager
-            .add_reminder(
-                Arc::clone(&ctx),
-                command.channel_id,
-                Reminder {
-                    reminder_type: ReminderType::Once(later.naive_utc()),
-                    msg,
-                },
-            )
-            .await;
+        let content = if let Err(why) = template::validate(&msg) {
+            why
+        } else {
+            manager
+                .add_reminder(
+                    command.channel_id,
+                    Reminder {
+                        author: Some(command.user.id),
+                        mentions,
+                        nag,
+                        ..Reminder::new(ReminderType::Once(later.naive_utc()), msg)
+                    },
+                )
+                .await;
+            "done".to_string()
+        };
 
         if let Err(why) = command
             .create_interaction_response(&ctx.http, move |response| {
-                response.interaction_response_data(|message| message.content("done"))
+                response.interaction_response_data(|message| message.content(content))
             })
             .await
         {
-            println!("Cannot respond to slash command: {:#?}", why);
+            println!("Cannot respond to slash command: {why:#?}");
         }
     }
 }
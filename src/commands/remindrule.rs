@@ -0,0 +1,117 @@
+use super::*;
+use crate::{
+    reminder::{Reminder, ReminderType},
+    rrule::RRule,
+    template, time_parser,
+};
+use chrono::Utc;
+
+pub struct RemindRule;
+
+#[async_trait]
+impl Command for RemindRule {
+    fn name(&self) -> &'static str {
+        "remindrule"
+    }
+
+    fn create(&self, command: &mut CreateApplicationCommand) {
+        command
+            .description("Sends message repeatedly following an iCalendar recurrence rule")
+            .create_option(|option| {
+                option
+                    .name("msg")
+                    .description("Message to be sent")
+                    .kind(CommandOptionType::String)
+                    .required(true)
+            })
+            .create_option(|option| {
+                option
+                    .name("rule")
+                    .description(
+                        "e.g. FREQ=WEEKLY;INTERVAL=2;BYDAY=TU, may include DTSTART and EXDATE",
+                    )
+                    .kind(CommandOptionType::String)
+                    .required(true)
+            })
+            .create_option(|option| {
+                option
+                    .name("start")
+                    .description("First time, e.g. tomorrow at 9am (defaults to now)")
+                    .kind(CommandOptionType::String)
+                    .required(false)
+            });
+        MentionOptions::create(command);
+        NagOptions::create(command);
+    }
+
+    async fn handle(
+        &self,
+        ctx: Arc<Context>,
+        manager: &Manager,
+        command: &ApplicationCommandInteraction,
+        options: HashMap<String, CommandDataOptionValue>,
+    ) {
+        let mentions = MentionOptions::parse(&options);
+        let nag = NagOptions::parse(&options);
+        let options = options
+            .into_iter()
+            .filter_map(|(k, v)| {
+                if let CommandDataOptionValue::String(s) = v {
+                    Some((k, s))
+                } else {
+                    None
+                }
+            })
+            .collect::<HashMap<_, _>>();
+        let tz = manager.user_tz(command.user.id, command.channel_id).await;
+
+        // The msg and rule options are required, we are guaranteed to have them
+        let mut source = options.get("rule").unwrap().clone();
+        let start = match options.get("start") {
+            Some(start) => time_parser::parse(start, tz, Utc::now()),
+            None => Ok(Utc::now()),
+        };
+
+        let content = match start {
+            Ok(start) => {
+                // The start is written in the user's timezone like the rest of the rule
+                if !source.to_uppercase().contains("DTSTART") {
+                    let start = start.with_timezone(&tz).format("%Y%m%dT%H%M00");
+                    source = format!("DTSTART:{start}\n{source}");
+                }
+
+                match source.parse::<RRule>() {
+                    Ok(rrule) => {
+                        let msg = options.get("msg").unwrap().clone();
+                        let reminder = Reminder {
+                            author: Some(command.user.id),
+                            mentions,
+                            nag,
+                            ..Reminder::new(ReminderType::Recurring(rrule), msg)
+                        };
+                        if let Err(why) = template::validate(&reminder.msg) {
+                            why
+                        } else if reminder.next_after(tz, Utc::now()).is_none() {
+                            "the reminder would never be sent".to_string()
+                        } else {
+                            manager.add_reminder(command.channel_id, reminder).await;
+
+                            "done".to_string()
+                        }
+                    }
+                    Err(why) => format!("invalid rule: {why}"),
+                }
+            }
+            Err(why) => why.to_string(),
+        };
+
+        if let Err(why) = command
+            .create_interaction_response(&ctx.http, move |response| {
+                response.interaction_response_data(|message| message.content(content))
+            })
+            .await
+        {
+            println!("Cannot respond to slash command: {why:#?}");
+        }
+    }
+}
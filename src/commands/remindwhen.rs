@@ -0,0 +1,85 @@
+use super::*;
+use crate::{
+    reminder::{Reminder, ReminderType},
+    template, time_parser,
+};
+use chrono::Utc;
+
+pub struct RemindWhen;
+
+#[async_trait]
+impl Command for RemindWhen {
+    fn name(&self) -> &'static str {
+        "remind"
+    }
+
+    fn create(&self, command: &mut CreateApplicationCommand) {
+        command
+            .description("Sends message once at a time written in plain words")
+            .create_option(|option| {
+                option
+                    .name("msg")
+                    .description("Message to be sent")
+                    .kind(CommandOptionType::String)
+                    .required(true)
+            })
+            .create_option(|option| {
+                option
+                    .name("when")
+                    .description("e.g. tomorrow at 9am, in 2h30m, next friday, dec 1 2pm")
+                    .kind(CommandOptionType::String)
+                    .required(true)
+            });
+        MentionOptions::create(command);
+        NagOptions::create(command);
+    }
+
+    async fn handle(
+        &self,
+        ctx: Arc<Context>,
+        manager: &Manager,
+        command: &ApplicationCommandInteraction,
+        options: HashMap<String, CommandDataOptionValue>,
+    ) {
+        let get = |name: &str| {
+            // Both options are required, we are guaranteed to have them
+            if let Some(CommandDataOptionValue::String(s)) = options.get(name) {
+                s.clone()
+            } else {
+                panic!("Expected {name} to be string")
+            }
+        };
+        let msg = get("msg");
+        let tz = manager.user_tz(command.user.id, command.channel_id).await;
+
+        let at = template::validate(&msg)
+            .and_then(|()| time_parser::parse(&get("when"), tz, Utc::now()).map_err(Into::into));
+        let content = match at {
+            Ok(at) => {
+                manager
+                    .add_reminder(
+                        command.channel_id,
+                        Reminder {
+                            author: Some(command.user.id),
+                            mentions: MentionOptions::parse(&options),
+                            nag: NagOptions::parse(&options),
+                            ..Reminder::new(ReminderType::Once(at.naive_utc()), msg)
+                        },
+                    )
+                    .await;
+
+                format!("done, see you <t:{}:F>", at.timestamp())
+            }
+            Err(why) => why,
+        };
+
+        if let Err(why) = command
+            .create_interaction_response(&ctx.http, move |response| {
+                response.interaction_response_data(|message| message.content(content))
+            })
+            .await
+        {
+            println!("Cannot respond to slash command: {why:#?}");
+        }
+    }
+}
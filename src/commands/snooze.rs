@@ -0,0 +1,85 @@
+use super::*;
+use crate::reminder::{MAX_SNOOZE_BUTTONS, MAX_SNOOZE_MINS};
+
+// Parses durations like "5", "90m", "2h" or "1d" into minutes
+fn parse_duration(duration: &str) -> Option<u32> {
+    let (value, unit) = match duration.find(|c: char| !c.is_ascii_digit()) {
+        Some(i) => duration.split_at(i),
+        None => (duration, "m"),
+    };
+    let factor = match unit {
+        "m" | "min" => 1,
+        "h" => 60,
+        "d" => 24 * 60,
+        _ => return None,
+    };
+
+    value
+        .parse::<u32>()
+        .ok()?
+        .checked_mul(factor)
+        .filter(|mins| (1..=MAX_SNOOZE_MINS).contains(mins))
+}
+
+pub struct Snooze;
+
+#[async_trait]
+impl Command for Snooze {
+    fn name(&self) -> &'static str {
+        "snooze"
+    }
+
+    fn create(&self, command: &mut CreateApplicationCommand) {
+        command
+            .description("Set the snooze buttons of this channel's reminders")
+            .create_option(|option| {
+                option
+                    .name("durations")
+                    .description("Up to 4 durations, e.g. 10m 1h 1d, leave empty for 5m 15m 30m")
+                    .kind(CommandOptionType::String)
+                    .required(false)
+            });
+    }
+
+    async fn handle(
+        &self,
+        ctx: Arc<Context>,
+        manager: &Manager,
+        command: &ApplicationCommandInteraction,
+        options: HashMap<String, CommandDataOptionValue>,
+    ) {
+        let durations = match options.get("durations") {
+            Some(CommandDataOptionValue::String(s)) => Some(
+                s.split_whitespace()
+                    .map(|d| parse_duration(&d.to_lowercase()))
+                    .collect::<Option<Vec<_>>>(),
+            ),
+            _ => None,
+        };
+
+        let content = match durations {
+            Some(None) => "invalid duration, use e.g. 10m, 1h or 1d, a week at most",
+            Some(Some(mins)) if mins.len() > MAX_SNOOZE_BUTTONS => "at most 4 durations",
+            Some(Some(mins)) if mins.is_empty() => "no durations given",
+            Some(Some(mut mins)) => {
+                mins.sort_unstable();
+                mins.dedup();
+                manager.set_snooze(command.channel_id, Some(mins)).await;
+                "done"
+            }
+            None => {
+                manager.set_snooze(command.channel_id, None).await;
+                "done"
+            }
+        };
+
+        if let Err(why) = command
+            .create_interaction_response(&ctx.http, move |response| {
+                response.interaction_response_data(|message| message.content(content))
+            })
+            .await
+        {
+            println!("Cannot respond to slash command: {why:#?}");
+        }
+    }
+}
@@ -0,0 +1,114 @@
+use super::*;
+use crate::reminder::Target;
+use serenity::model::{
+    channel::ChannelType,
+    id::{ChannelId, UserId},
+    Permissions,
+};
+
+const THREAD_TYPES: [ChannelType; 3] = [
+    ChannelType::PublicThread,
+    ChannelType::PrivateThread,
+    ChannelType::NewsThread,
+];
+
+// Permissions needed to post in a channel of the given type
+fn needed(kind: ChannelType) -> Permissions {
+    if THREAD_TYPES.contains(&kind) {
+        Permissions::VIEW_CHANNEL | Permissions::SEND_MESSAGES_IN_THREADS
+    } else {
+        Permissions::VIEW_CHANNEL | Permissions::SEND_MESSAGES
+    }
+}
+
+// Permissions a user has in a channel, threads take theirs from their parent
+async fn permissions_in(
+    ctx: &Context,
+    channel_id: ChannelId,
+    user_id: UserId,
+) -> Option<Permissions> {
+    let mut channel = channel_id.to_channel(ctx).await.ok()?.guild()?;
+    if let (true, Some(parent_id)) = (THREAD_TYPES.contains(&channel.kind), channel.parent_id) {
+        channel = parent_id.to_channel(ctx).await.ok()?.guild()?;
+    }
+    let guild = channel.guild_id.to_partial_guild(ctx).await.ok()?;
+    let member = guild.member(ctx, user_id).await.ok()?;
+
+    guild.user_permissions_in(&channel, &member).ok()
+}
+
+// Options of the commands creating reminders that send them somewhere else than
+// the channel they are created in
+pub(super) struct TargetOptions;
+
+impl TargetOptions {
+    pub(super) fn create(command: &mut CreateApplicationCommand) {
+        command
+            .create_option(|option| {
+                option
+                    .name("target")
+                    .description("Channel or thread to send it to instead of this one")
+                    .kind(CommandOptionType::Channel)
+                    .channel_types(&[
+                        ChannelType::Text,
+                        ChannelType::News,
+                        ChannelType::PublicThread,
+                        ChannelType::PrivateThread,
+                        ChannelType::NewsThread,
+                    ])
+                    .required(false)
+            })
+            .create_option(|option| {
+                option
+                    .name("dm")
+                    .description("Send it to you in direct messages instead of this channel")
+                    .kind(CommandOptionType::Boolean)
+                    .required(false)
+            });
+    }
+
+    // Checks that both the user and the bot can post to the chosen target
+    pub(super) async fn parse(
+        ctx: &Context,
+        command: &ApplicationCommandInteraction,
+        options: &HashMap<String, CommandDataOptionValue>,
+    ) -> Result<Option<Target>, &'static str> {
+        let dm = matches!(
+            options.get("dm"),
+            Some(CommandDataOptionValue::Boolean(true))
+        );
+
+        match (options.get("target"), dm) {
+            (Some(_), true) => Err("choose either a target or dm, not both"),
+            (None, true) => {
+                command
+                    .user
+                    .create_dm_channel(ctx)
+                    .await
+                    .map_err(|_| "I cannot send you direct messages")?;
+                Ok(Some(Target::Dm(command.user.id)))
+            }
+            (Some(CommandDataOptionValue::Channel(channel)), false)
+                if channel.id != command.channel_id =>
+            {
+                let needed = needed(channel.kind);
+                if !channel.permissions.is_some_and(|p| p.contains(needed)) {
+                    return Err("you cannot post in that channel");
+                }
+
+                let bot = ctx
+                    .http
+                    .get_current_user()
+                    .await
+                    .map_err(|_| "cannot check my permissions, try again later")?;
+                match permissions_in(ctx, channel.id, bot.id).await {
+                    Some(permissions) if permissions.contains(needed) => {
+                        Ok(Some(Target::Channel(channel.id)))
+                    }
+                    _ => Err("I cannot post in that channel"),
+                }
+            }
+            _ => Ok(None),
+        }
+    }
+}
@@ -4,7 +4,7 @@ pub struct Tz;
 
 #[async_trait]
 impl Command for Tz {
-    fn name(&self) -> &str {
+    fn name(&self) -> &'static str {
         "tz"
     }
 
@@ -15,7 +15,7 @@ impl Command for Tz {
                 option
                     .name("tz")
                     .description("IANA timezone name")
-                    .kind(ApplicationCommandOptionType::String)
+                    .kind(CommandOptionType::String)
                     .required(true)
             });
     }
@@ -25,16 +25,13 @@ impl Command for Tz {
         ctx: Arc<Context>,
         manager: &Manager,
         command: &ApplicationCommandInteraction,
-        options: HashMap<String, ApplicationCommandInteractionDataOptionValue>,
+        options: HashMap<String, CommandDataOptionValue>,
     ) {
-        let tz_str = if let ApplicationCommandInteractionDataOptionValue::String(s) = &options["tz"]
-        {
-            s
-        } else {
+        let CommandDataOptionValue::String(tz_str) = &options["tz"] else {
             panic!("Expected string option");
         };
 
-        let content = if manager.set_channel_tz(command.channel_id, &tz_str)
+        let content = if manager.set_channel_tz(command.channel_id, tz_str)
             .await
             .is_ok()
         {
@@ -50,7 +47,7 @@ impl Command for Tz {
             })
             .await
         {
-            println!("Cannot respond to slash command: {:#?}", why);
+            println!("Cannot respond to slash command: {why:#?}");
         }
     }
 }
@@ -0,0 +1,63 @@
+use super::*;
+use crate::{
+    reminder::Webhook,
+    sink::{resolve, SendError},
+};
+use serenity::model::Permissions;
+
+// Options of the commands creating reminders that post them to an HTTP endpoint
+pub(super) struct WebhookOptions;
+
+impl WebhookOptions {
+    pub(super) fn create(command: &mut CreateApplicationCommand) {
+        command
+            .create_option(|option| {
+                option
+                    .name("webhook")
+                    .description("URL to post it to as JSON instead of sending it here")
+                    .kind(CommandOptionType::String)
+                    .required(false)
+            })
+            .create_option(|option| {
+                option
+                    .name("discord")
+                    .description("Send it here as well when posting it to a webhook")
+                    .kind(CommandOptionType::Boolean)
+                    .required(false)
+            });
+    }
+
+    // Webhooks get a new secret to sign their requests with. Only members who can
+    // manage the channel's webhooks may make the bot post somewhere, and never to
+    // internal addresses
+    pub(super) async fn parse(
+        command: &ApplicationCommandInteraction,
+        options: &HashMap<String, CommandDataOptionValue>,
+    ) -> Result<Option<Webhook>, &'static str> {
+        let Some(CommandDataOptionValue::String(url)) = options.get("webhook") else {
+            return Ok(None);
+        };
+        let permissions = command
+            .member
+            .as_ref()
+            .and_then(|member| member.permissions);
+        if !permissions.is_some_and(|p| p.contains(Permissions::MANAGE_WEBHOOKS)) {
+            return Err("you need the Manage Webhooks permission to use webhooks");
+        }
+
+        match resolve(url.trim()).await {
+            Ok((url, _)) => Ok(Some(Webhook {
+                url: url.to_string(),
+                secret: secret(),
+                discord: matches!(
+                    options.get("discord"),
+                    Some(CommandDataOptionValue::Boolean(true))
+                ),
+            })),
+            Err(SendError::Transient(_)) => Err("cannot resolve the webhook's host"),
+            Err(SendError::Permanent(_)) => {
+                Err("invalid webhook, use an http or https URL with a public address")
+            }
+        }
+    }
+}
@@ -0,0 +1,261 @@
+use super::{migrations, Storage};
+use crate::reminder::{ChannelData, ChannelSettings, DeadLetter, Reminder};
+use ahash::AHasher;
+use chrono_tz::{ParseError, Tz};
+use serde::{Deserialize, Serialize};
+use serenity::{
+    async_trait,
+    model::id::{ChannelId, UserId},
+};
+use slotmap::DefaultKey;
+use std::{
+    collections::HashMap,
+    hash::BuildHasherDefault,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+use tokio::{
+    fs::{self, File},
+    io::AsyncWriteExt,
+};
+
+type Data = HashMap<ChannelId, ChannelData, BuildHasherDefault<AHasher>>;
+type Users = HashMap<UserId, Tz, BuildHasherDefault<AHasher>>;
+
+/// On-disk layout of the database, see [`migrations`] for older layouts
+#[derive(Default, Serialize, Deserialize)]
+pub struct Envelope<D = Data, U = Users> {
+    pub version: u64,
+    pub channels: D,
+    /// Timezones users set for themselves
+    #[serde(default)]
+    pub users: U,
+}
+
+fn with_extension(path: &Path, ext: &str) -> PathBuf {
+    let mut path = path.as_os_str().to_owned();
+    path.push(ext);
+    path.into()
+}
+
+// Reads, migrates and parses a database file, a missing or empty file is not
+// an error and yields no data
+//
+// Files in an older layout are copied to a backup named after their version
+// before being migrated, since the migrated data replaces them on the next write
+async fn load(path: &Path) -> Result<Option<Envelope>, String> {
+    let contents = match fs::read_to_string(path).await {
+        Ok(contents) if contents.trim().is_empty() => return Ok(None),
+        Ok(contents) => contents,
+        Err(why) if why.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(why) => return Err(format!("{}: {why}", path.display())),
+    };
+
+    let data =
+        serde_json::from_str(&contents).map_err(|why| format!("{}: {why}", path.display()))?;
+    let version = migrations::version(&data);
+    if version < migrations::CURRENT_VERSION {
+        let backup = with_extension(path, &format!(".v{version}.bak"));
+        fs::copy(path, &backup)
+            .await
+            .map_err(|why| format!("{}: {why}", backup.display()))?;
+        println!(
+            "Migrating database from version {version}, backup at {}",
+            backup.display()
+        );
+    }
+
+    let data = migrations::migrate(data).map_err(|why| format!("{}: {why}", path.display()))?;
+    serde_json::from_value::<Envelope>(data)
+        .map(Some)
+        .map_err(|why| format!("{}: {why}", path.display()))
+}
+
+/// Stores every channel as a single JSON document, rewritten on each change
+///
+/// Writes go to a temporary file that is synced and then renamed over the
+/// database, so the file on disk is always either the old or the new version.
+/// The previous version is kept next to it with a `.bak` extension
+pub struct JsonDb {
+    path: PathBuf,
+    data: Data,
+    users: Users,
+}
+
+impl JsonDb {
+    pub async fn open(db_path: &str) -> Self {
+        let path = PathBuf::from(db_path);
+        let backup = with_extension(&path, ".bak");
+
+        // A database that exists but cannot be read is never replaced with an empty
+        // one, we either recover from the backup or refuse to start
+        let envelope = match load(&path).await {
+            Ok(Some(envelope)) => envelope,
+            Ok(None) => load(&backup)
+                .await
+                .expect("Database file is missing and its backup cannot be read")
+                .unwrap_or_default(),
+            Err(why) => {
+                println!("Cannot read database file, trying backup: {why}");
+                let envelope = load(&backup)
+                    .await
+                    .and_then(|envelope| envelope.ok_or_else(|| "backup is missing".to_string()))
+                    .unwrap_or_else(|why| {
+                        panic!("Refusing to start with an unreadable database: {why}")
+                    });
+
+                // Keep the unreadable file around for inspection
+                fs::rename(&path, with_extension(&path, ".corrupt"))
+                    .await
+                    .expect("Error moving unreadable database file");
+
+                envelope
+            }
+        };
+
+        let mut db = Self {
+            path,
+            data: envelope.channels,
+            users: envelope.users,
+        };
+        db.persist().await;
+
+        db
+    }
+
+    async fn persist(&mut self) {
+        let content = serde_json::to_vec(&Envelope {
+            version: migrations::CURRENT_VERSION,
+            channels: &self.data,
+            users: &self.users,
+        })
+        .expect("Error serializing data");
+
+        let tmp = with_extension(&self.path, ".tmp");
+        let mut file = File::create(&tmp)
+            .await
+            .expect("Error creating temporary database file");
+        file.write_all(&content)
+            .await
+            .expect("Error writing to temporary database file");
+        file.sync_all()
+            .await
+            .expect("Error syncing temporary database file");
+
+        // Copying rather than renaming means the database path always points to a
+        // complete file, even if we die between these two steps
+        match fs::copy(&self.path, with_extension(&self.path, ".bak")).await {
+            Err(why) if why.kind() != ErrorKind::NotFound => {
+                println!("Cannot back up database file: {why}");
+            }
+            _ => (),
+        }
+
+        fs::rename(&tmp, &self.path)
+            .await
+            .expect("Error replacing database file");
+
+        // The rename itself is only durable once the directory entry is synced
+        if let Some(dir) = self.path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            if let Ok(dir) = File::open(dir).await {
+                dir.sync_all().await.ok();
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl Storage for JsonDb {
+    async fn insert(&mut self, channel_id: ChannelId, reminder: Reminder) -> DefaultKey {
+        let key = self
+            .data
+            .entry(channel_id)
+            .or_default()
+            .reminders
+            .insert(reminder);
+        self.persist().await;
+
+        key
+    }
+
+    async fn update(&mut self, channel_id: ChannelId, key: DefaultKey, reminder: Reminder) {
+        if let Some(slot) = self
+            .data
+            .get_mut(&channel_id)
+            .and_then(|cd| cd.reminders.get_mut(key))
+        {
+            *slot = reminder;
+            self.persist().await;
+        }
+    }
+
+    async fn remove(&mut self, channel_id: ChannelId, key: DefaultKey) {
+        self.data.entry(channel_id).and_modify(|r| {
+            r.reminders.remove(key);
+        });
+        self.persist().await;
+    }
+
+    fn tz(&self, channel_id: ChannelId) -> Option<Tz> {
+        self.data.get(&channel_id).map(|cd| cd.tz)
+    }
+
+    async fn set_tz(&mut self, channel_id: ChannelId, tz_str: &str) -> Result<(), ParseError> {
+        self.data.entry(channel_id).or_default().set_tz(tz_str)?;
+        self.persist().await;
+
+        Ok(())
+    }
+
+    async fn set_settings(&mut self, channel_id: ChannelId, settings: ChannelSettings) {
+        self.data.entry(channel_id).or_default().settings = settings;
+        self.persist().await;
+    }
+
+    async fn push_dead_letter(&mut self, channel_id: ChannelId, dead_letter: DeadLetter) {
+        self.data
+            .entry(channel_id)
+            .or_default()
+            .dead_letters
+            .push(dead_letter);
+        self.persist().await;
+    }
+
+    async fn take_dead_letter(
+        &mut self,
+        channel_id: ChannelId,
+        index: usize,
+    ) -> Option<DeadLetter> {
+        let dead_letters = &mut self.data.get_mut(&channel_id)?.dead_letters;
+        let dead_letter = (index < dead_letters.len()).then(|| dead_letters.remove(index))?;
+        self.persist().await;
+
+        Some(dead_letter)
+    }
+
+    fn user_tz(&self, user_id: UserId) -> Option<Tz> {
+        self.users.get(&user_id).copied()
+    }
+
+    async fn set_user_tz(
+        &mut self,
+        user_id: UserId,
+        tz_str: Option<&str>,
+    ) -> Result<(), ParseError> {
+        match tz_str {
+            Some(tz_str) => self.users.insert(user_id, tz_str.parse()?),
+            None => self.users.remove(&user_id),
+        };
+        self.persist().await;
+
+        Ok(())
+    }
+
+    fn channels(&self) -> Box<dyn Iterator<Item = (&ChannelId, &ChannelData)> + '_> {
+        Box::new(self.data.iter())
+    }
+
+    fn channel_data(&self, channel_id: ChannelId) -> Option<&ChannelData> {
+        self.data.get(&channel_id)
+    }
+}
@@ -0,0 +1,136 @@
+use serde_json::{Map, Value};
+
+// Each entry upgrades the layout of the version equal to its index to the next
+// one, new migrations are only ever appended
+const MIGRATIONS: &[fn(Value) -> Value] = &[v0_to_v1, v1_to_v2];
+
+/// Layout version written by this build
+pub const CURRENT_VERSION: u64 = MIGRATIONS.len() as u64;
+
+// Version 0 is the bare channel map written before the envelope existed
+fn v0_to_v1(data: Value) -> Value {
+    let mut envelope = Map::new();
+    envelope.insert("version".to_string(), 1.into());
+    envelope.insert("channels".to_string(), data);
+
+    envelope.into()
+}
+
+// Version 2 covers the fields added after version 1 up to webhook sinks:
+// catch-up policies, dead letters, pauses and occurrence limits, recurrence
+// rules, user timezones, owners, mentions, nags, snooze options, embeds and
+// delivery targets. They all have defaults so no data changes, but older
+// builds refuse the new version instead of dropping those fields on write
+fn v1_to_v2(mut data: Value) -> Value {
+    data["version"] = 2.into();
+
+    data
+}
+
+/// Version of a stored document, documents without an envelope are version 0
+pub fn version(data: &Value) -> u64 {
+    data.get("version")
+        .filter(|_| data.get("channels").is_some())
+        .and_then(Value::as_u64)
+        .unwrap_or(0)
+}
+
+/// Upgrades a stored document to the current layout
+pub fn migrate(mut data: Value) -> Result<Value, String> {
+    let from = version(&data);
+    if from > CURRENT_VERSION {
+        return Err(format!(
+            "database version {from} is newer than the supported version {CURRENT_VERSION}"
+        ));
+    }
+
+    for (to, migration) in MIGRATIONS.iter().enumerate().skip(from.try_into().unwrap()) {
+        data = migration(data);
+        debug_assert_eq!(version(&data), to as u64 + 1);
+    }
+
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        db::json::Envelope,
+        reminder::{CatchUp, ReminderType, SinkKind},
+    };
+    use serde_json::json;
+    use serenity::model::id::{ChannelId, UserId};
+
+    const V0: &str = include_str!("fixtures/v0.json");
+    const V1: &str = include_str!("fixtures/v1.json");
+    const V2: &str = include_str!("fixtures/v2.json");
+
+    fn load(fixture: &str) -> Envelope {
+        let data = migrate(serde_json::from_str(fixture).unwrap()).unwrap();
+        assert_eq!(version(&data), CURRENT_VERSION);
+        serde_json::from_value(data).unwrap()
+    }
+
+    fn check(envelope: &Envelope) {
+        let channel = &envelope.channels[&ChannelId(896_183_942_372_290_611)];
+        assert_eq!(channel.tz, chrono_tz::Europe::Madrid);
+
+        let mut reminders = channel.reminders.values().collect::<Vec<_>>();
+        reminders.sort_by(|a, b| a.msg.cmp(&b.msg));
+        assert_eq!(reminders.len(), 2);
+        assert!(matches!(
+            &reminders[0].reminder_type,
+            ReminderType::Scheduled(sched) if sched.to_string() == "0 30 9 * * Mon-Fri *"
+        ));
+        assert!(matches!(reminders[1].reminder_type, ReminderType::Once(_)));
+
+        let empty = &envelope.channels[&ChannelId(896_183_942_372_290_612)];
+        assert_eq!(empty.tz, chrono_tz::UTC);
+        assert!(empty.reminders.is_empty());
+    }
+
+    #[test]
+    fn v0_is_migrated() {
+        assert_eq!(version(&serde_json::from_str(V0).unwrap()), 0);
+        check(&load(V0));
+    }
+
+    #[test]
+    fn v1_is_migrated() {
+        assert_eq!(version(&serde_json::from_str(V1).unwrap()), 1);
+        check(&load(V1));
+    }
+
+    #[test]
+    fn v2_keeps_every_field() {
+        let data: Value = serde_json::from_str(V2).unwrap();
+        assert_eq!(version(&data), 2);
+        let envelope = load(V2);
+
+        let channel = &envelope.channels[&ChannelId(896_183_942_372_290_611)];
+        assert_eq!(channel.settings.catch_up, CatchUp::Skip);
+        assert_eq!(channel.settings.snooze_mins(), [10, 60]);
+        assert_eq!(
+            channel.dead_letters[0].author,
+            Some(UserId(150_000_000_000_000_001))
+        );
+        assert_eq!(channel.dead_letters[0].sink, Some(SinkKind::Webhook));
+        let standup = channel.reminders.values().find(|r| r.paused).unwrap();
+        assert_eq!(standup.remaining(), Some(88));
+        assert_eq!(standup.nag.as_ref().unwrap().attempts, 1);
+        assert!(standup.webhook.as_ref().unwrap().discord);
+        assert_eq!(
+            envelope.users[&UserId(150_000_000_000_000_001)],
+            chrono_tz::America::New_York
+        );
+
+        // Nothing is lost or changed when the data is written back
+        assert_eq!(serde_json::to_value(&envelope).unwrap(), data);
+    }
+
+    #[test]
+    fn newer_versions_are_rejected() {
+        assert!(migrate(json!({ "version": CURRENT_VERSION + 1, "channels": {} })).is_err());
+    }
+}
@@ -0,0 +1,155 @@
+use crate::reminder::{ChannelData, ChannelSettings, DeadLetter, Reminder};
+use chrono_tz::{ParseError, Tz};
+use serenity::{
+    async_trait,
+    model::id::{ChannelId, UserId},
+};
+use slotmap::DefaultKey;
+use std::{iter::repeat, str::FromStr};
+
+mod json;
+mod migrations;
+mod sqlite;
+
+pub use json::JsonDb;
+pub use sqlite::SqliteDb;
+
+/// Everything the reminder manager needs from persistent storage
+///
+/// Implementations are expected to keep the data they serve in memory, reads
+/// are synchronous and only writes go through to the backing store
+#[async_trait]
+pub trait Storage: Send + Sync {
+    async fn insert(&mut self, channel_id: ChannelId, reminder: Reminder) -> DefaultKey;
+
+    /// Replaces a reminder, does nothing if it no longer exists
+    async fn update(&mut self, channel_id: ChannelId, key: DefaultKey, reminder: Reminder);
+
+    async fn remove(&mut self, channel_id: ChannelId, key: DefaultKey);
+
+    fn tz(&self, channel_id: ChannelId) -> Option<Tz>;
+
+    async fn set_tz(&mut self, channel_id: ChannelId, tz_str: &str) -> Result<(), ParseError>;
+
+    /// Timezone a user set for themselves
+    fn user_tz(&self, user_id: UserId) -> Option<Tz>;
+
+    /// Sets a user's timezone, or removes it so the channel's timezone applies
+    async fn set_user_tz(
+        &mut self,
+        user_id: UserId,
+        tz_str: Option<&str>,
+    ) -> Result<(), ParseError>;
+
+    /// Timezone a reminder fires in: its author's if they set one, otherwise the
+    /// channel's
+    fn reminder_tz(&self, channel_id: ChannelId, reminder: &Reminder) -> Option<Tz> {
+        reminder
+            .author
+            .and_then(|author| self.user_tz(author))
+            .or_else(|| self.tz(channel_id))
+    }
+
+    async fn set_settings(&mut self, channel_id: ChannelId, settings: ChannelSettings);
+
+    async fn push_dead_letter(&mut self, channel_id: ChannelId, dead_letter: DeadLetter);
+
+    /// Removes and returns the dead letter at the given position
+    async fn take_dead_letter(&mut self, channel_id: ChannelId, index: usize)
+        -> Option<DeadLetter>;
+
+    fn channels(&self) -> Box<dyn Iterator<Item = (&ChannelId, &ChannelData)> + '_>;
+
+    /// Every reminder along with its channel and the timezone it fires in
+    fn iter(&self) -> Box<dyn Iterator<Item = (&ChannelId, Tz, DefaultKey, &Reminder)> + '_> {
+        Box::new(self.channels().flat_map(move |(k, cd)| {
+            repeat(k)
+                .zip(cd.reminders.iter())
+                .map(move |(k, (d, v))| (k, self.reminder_tz(*k, v).unwrap_or(cd.tz), d, v))
+        }))
+    }
+
+    fn channel_data(&self, channel_id: ChannelId) -> Option<&ChannelData>;
+}
+
+/// Storage backend, selected through the `DB_BACKEND` environment variable
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    #[default]
+    Json,
+    Sqlite,
+}
+
+impl FromStr for Backend {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "json" => Ok(Self::Json),
+            "sqlite" => Ok(Self::Sqlite),
+            _ => Err(format!("unknown database backend: {s}")),
+        }
+    }
+}
+
+impl Backend {
+    pub async fn open(self, db_path: &str) -> Box<dyn Storage> {
+        match self {
+            Self::Json => Box::new(JsonDb::open(db_path).await),
+            Self::Sqlite => Box::new(SqliteDb::open(db_path)),
+        }
+    }
+}
+
+/// Database file in the temporary directory for tests, opened with the JSON
+/// backend unless a test opens its path itself. The file and the ones written
+/// next to it are removed when it is dropped, even if the test panics
+#[cfg(test)]
+pub struct TempDb(std::path::PathBuf);
+
+#[cfg(test)]
+impl TempDb {
+    /// Names must be unique across tests, since they run at the same time
+    pub fn new(name: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("metis-{name}-{}.json", std::process::id()));
+        let db = Self(path);
+        // Leftovers of an earlier run would leak into this one
+        db.remove();
+        db
+    }
+
+    pub fn path(&self) -> &str {
+        self.0.to_str().expect("Temporary path is not UTF-8")
+    }
+
+    pub async fn open(&self) -> Box<dyn Storage> {
+        Box::new(JsonDb::open(self.path()).await)
+    }
+
+    // Removes the database along with every file the backends write next to it,
+    // such as backups of each migrated version, which all start with its name
+    fn remove(&self) {
+        let (Some(dir), Some(name)) = (self.0.parent(), self.0.file_name()) else {
+            return;
+        };
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            return;
+        };
+        for entry in entries.flatten() {
+            if entry
+                .file_name()
+                .as_encoded_bytes()
+                .starts_with(name.as_encoded_bytes())
+            {
+                std::fs::remove_file(entry.path()).ok();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+impl Drop for TempDb {
+    fn drop(&mut self) {
+        self.remove();
+    }
+}
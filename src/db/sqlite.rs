@@ -0,0 +1,585 @@
+use super::Storage;
+use crate::reminder::{ChannelData, ChannelSettings, DeadLetter, Reminder};
+use ahash::AHasher;
+use chrono_tz::{ParseError, Tz};
+use rusqlite::{params, Connection, Transaction};
+use serenity::{
+    async_trait,
+    model::id::{ChannelId, UserId},
+};
+use slotmap::{DefaultKey, Key, KeyData, SlotMap};
+use std::{collections::HashMap, hash::BuildHasherDefault, sync::Mutex};
+
+// Each entry upgrades the schema from the version equal to its index to the
+// next one, the current version is kept in SQLite's `user_version`
+const MIGRATIONS: &[&str] = &[
+    "
+    CREATE TABLE IF NOT EXISTS channels (
+        id INTEGER PRIMARY KEY
+    );
+    CREATE TABLE IF NOT EXISTS timezones (
+        channel_id INTEGER PRIMARY KEY REFERENCES channels (id),
+        tz TEXT NOT NULL
+    );
+    CREATE TABLE IF NOT EXISTS reminders (
+        channel_id INTEGER NOT NULL REFERENCES channels (id),
+        key INTEGER NOT NULL,
+        data TEXT NOT NULL,
+        PRIMARY KEY (channel_id, key)
+    );
+    ",
+    "ALTER TABLE channels ADD COLUMN settings TEXT;",
+    "
+    CREATE TABLE dead_letters (
+        channel_id INTEGER PRIMARY KEY REFERENCES channels (id),
+        data TEXT NOT NULL
+    );
+    ",
+    "
+    CREATE TABLE user_timezones (
+        user_id INTEGER PRIMARY KEY,
+        tz TEXT NOT NULL
+    );
+    ",
+    "
+    CREATE TABLE vacant_slots (
+        channel_id INTEGER NOT NULL REFERENCES channels (id),
+        idx INTEGER NOT NULL,
+        version INTEGER NOT NULL,
+        PRIMARY KEY (channel_id, idx)
+    );
+    ",
+];
+
+fn migrate(conn: &mut Connection) {
+    let version: usize = conn
+        .query_row("PRAGMA user_version", [], |row| row.get(0))
+        .expect("Error reading database version");
+    assert!(
+        version <= MIGRATIONS.len(),
+        "Database version {version} is newer than the supported version {}",
+        MIGRATIONS.len()
+    );
+
+    for (from, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+        let tx = conn.transaction().expect("Error starting migration");
+        tx.execute_batch(migration)
+            .and_then(|()| tx.pragma_update(None, "user_version", from + 1))
+            .and_then(|()| tx.commit())
+            .expect("Error migrating database");
+    }
+}
+
+// SQLite only has signed integers, both snowflakes and slotmap keys are stored
+// with their bits reinterpreted rather than converted
+fn to_sql_id(id: u64) -> i64 {
+    i64::from_ne_bytes(id.to_ne_bytes())
+}
+
+fn from_sql_id(id: i64) -> u64 {
+    u64::from_ne_bytes(id.to_ne_bytes())
+}
+
+// Slot index and version of a key, the two halves of its FFI representation
+fn split_key(key: DefaultKey) -> (u32, u32) {
+    let ffi = key.data().as_ffi();
+    ((ffi & 0xffff_ffff) as u32, (ffi >> 32) as u32)
+}
+
+// Slotmaps cannot insert at a given key, so we rebuild one through its serde
+// representation: a list of slots where odd versions mark occupied slots and the
+// first slot is always the vacant sentinel. Vacant slots keep the version they
+// had when their reminder was removed, otherwise its key would be handed out
+// again and stale buttons would act on whatever reminder takes the slot
+fn rebuild_slotmap(slots: Vec<(u32, serde_json::Value)>) -> SlotMap<DefaultKey, Reminder> {
+    let vacant = serde_json::json!({ "value": null, "version": 0 });
+    let mut rebuilt = vec![vacant.clone()];
+    for (idx, slot) in slots {
+        let idx = idx as usize;
+        if rebuilt.len() <= idx {
+            rebuilt.resize(idx + 1, vacant.clone());
+        }
+        rebuilt[idx] = slot;
+    }
+
+    serde_json::from_value(serde_json::Value::Array(rebuilt)).expect("Error rebuilding reminders")
+}
+
+// Occupied and vacant slots of every channel's reminders, in their serde
+// representation
+fn load_slots(conn: &Connection) -> HashMap<ChannelId, Vec<(u32, serde_json::Value)>> {
+    let mut slots: HashMap<_, Vec<_>> = HashMap::new();
+
+    let mut stmt = conn
+        .prepare("SELECT channel_id, key, data FROM reminders")
+        .expect("Error preparing reminder query");
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, i64>(1)?,
+                row.get::<_, String>(2)?,
+            ))
+        })
+        .expect("Error querying reminders");
+    for row in rows {
+        let (channel_id, key, reminder) = row.expect("Error reading reminder");
+        let (idx, version) = split_key(KeyData::from_ffi(from_sql_id(key)).into());
+        let reminder: Reminder = serde_json::from_str(&reminder).expect("Error parsing reminder");
+        slots
+            .entry(ChannelId(from_sql_id(channel_id)))
+            .or_default()
+            .push((
+                idx,
+                serde_json::json!({ "value": reminder, "version": version }),
+            ));
+    }
+
+    let mut stmt = conn
+        .prepare("SELECT channel_id, idx, version FROM vacant_slots")
+        .expect("Error preparing vacant slot query");
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, u32>(1)?,
+                row.get::<_, u32>(2)?,
+            ))
+        })
+        .expect("Error querying vacant slots");
+    for row in rows {
+        let (channel_id, idx, version) = row.expect("Error reading vacant slot");
+        slots
+            .entry(ChannelId(from_sql_id(channel_id)))
+            .or_default()
+            .push((
+                idx,
+                serde_json::json!({ "value": null, "version": version }),
+            ));
+    }
+
+    slots
+}
+
+/// Stores channels, timezones and reminders in their own tables so that every
+/// change only touches the affected rows
+pub struct SqliteDb {
+    conn: Mutex<Connection>,
+    data: HashMap<ChannelId, ChannelData, BuildHasherDefault<AHasher>>,
+    users: HashMap<UserId, Tz, BuildHasherDefault<AHasher>>,
+}
+
+impl SqliteDb {
+    pub fn open(db_path: &str) -> Self {
+        let mut conn = Connection::open(db_path).expect("Error opening database file");
+        migrate(&mut conn);
+
+        let mut data: HashMap<_, ChannelData, _> = HashMap::default();
+        {
+            let mut stmt = conn
+                .prepare(
+                    "SELECT id, tz, settings FROM channels LEFT JOIN timezones ON id = channel_id",
+                )
+                .expect("Error preparing channel query");
+            let rows = stmt
+                .query_map([], |row| {
+                    Ok((
+                        row.get::<_, i64>(0)?,
+                        row.get::<_, Option<String>>(1)?,
+                        row.get::<_, Option<String>>(2)?,
+                    ))
+                })
+                .expect("Error querying channels");
+            for row in rows {
+                let (id, tz, settings) = row.expect("Error reading channel");
+                let mut channel = ChannelData::default();
+                if let Some(tz) = tz {
+                    channel.set_tz(&tz).expect("Error parsing stored timezone");
+                }
+                if let Some(settings) = settings {
+                    channel.settings =
+                        serde_json::from_str(&settings).expect("Error parsing channel settings");
+                }
+                data.insert(ChannelId(from_sql_id(id)), channel);
+            }
+        }
+
+        {
+            let mut stmt = conn
+                .prepare("SELECT channel_id, data FROM dead_letters")
+                .expect("Error preparing dead letter query");
+            let rows = stmt
+                .query_map([], |row| {
+                    Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+                })
+                .expect("Error querying dead letters");
+            for row in rows {
+                let (channel_id, dead_letters) = row.expect("Error reading dead letters");
+                data.entry(ChannelId(from_sql_id(channel_id)))
+                    .or_default()
+                    .dead_letters =
+                    serde_json::from_str(&dead_letters).expect("Error parsing dead letters");
+            }
+        }
+
+        let mut users = HashMap::default();
+        {
+            let mut stmt = conn
+                .prepare("SELECT user_id, tz FROM user_timezones")
+                .expect("Error preparing user timezone query");
+            let rows = stmt
+                .query_map([], |row| {
+                    Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+                })
+                .expect("Error querying user timezones");
+            for row in rows {
+                let (user_id, tz) = row.expect("Error reading user timezone");
+                let tz = tz.parse().expect("Error parsing stored timezone");
+                users.insert(UserId(from_sql_id(user_id)), tz);
+            }
+        }
+
+        for (channel_id, slots) in load_slots(&conn) {
+            data.entry(channel_id).or_default().reminders = rebuild_slotmap(slots);
+        }
+
+        Self {
+            conn: Mutex::new(conn),
+            data,
+            users,
+        }
+    }
+
+    fn execute(&self, sql: &str, params: impl rusqlite::Params) {
+        self.conn
+            .lock()
+            .expect("Database connection poisoned")
+            .execute(sql, params)
+            .expect("Error writing to database");
+    }
+
+    // Runs several writes at once, so that a crash cannot leave a slot both
+    // occupied and vacant or neither
+    fn transaction(&self, f: impl FnOnce(&Transaction) -> rusqlite::Result<()>) {
+        let mut conn = self.conn.lock().expect("Database connection poisoned");
+        let tx = conn.transaction().expect("Error starting transaction");
+        f(&tx)
+            .and_then(|()| tx.commit())
+            .expect("Error writing to database");
+    }
+
+    // Dead letters are few and only read as a whole, so each channel's list is
+    // stored in a single row
+    fn store_dead_letters(&self, channel_id: ChannelId) {
+        let dead_letters = self
+            .data
+            .get(&channel_id)
+            .map(|cd| serde_json::to_string(&cd.dead_letters))
+            .transpose()
+            .expect("Error serializing dead letters")
+            .unwrap_or_else(|| "[]".to_string());
+
+        self.ensure_channel(channel_id);
+        self.execute(
+            "INSERT OR REPLACE INTO dead_letters (channel_id, data) VALUES (?1, ?2)",
+            params![to_sql_id(channel_id.0), dead_letters],
+        );
+    }
+
+    fn ensure_channel(&self, channel_id: ChannelId) {
+        self.execute(
+            "INSERT OR IGNORE INTO channels (id) VALUES (?1)",
+            params![to_sql_id(channel_id.0)],
+        );
+    }
+}
+
+#[async_trait]
+impl Storage for SqliteDb {
+    async fn insert(&mut self, channel_id: ChannelId, reminder: Reminder) -> DefaultKey {
+        let content = serde_json::to_string(&reminder).expect("Error serializing reminder");
+        let key = self
+            .data
+            .entry(channel_id)
+            .or_default()
+            .reminders
+            .insert(reminder);
+
+        let (idx, _) = split_key(key);
+        self.ensure_channel(channel_id);
+        self.transaction(|tx| {
+            tx.execute(
+                "DELETE FROM vacant_slots WHERE channel_id = ?1 AND idx = ?2",
+                params![to_sql_id(channel_id.0), idx],
+            )?;
+            tx.execute(
+                "INSERT OR REPLACE INTO reminders (channel_id, key, data) VALUES (?1, ?2, ?3)",
+                params![
+                    to_sql_id(channel_id.0),
+                    to_sql_id(key.data().as_ffi()),
+                    content
+                ],
+            )?;
+
+            Ok(())
+        });
+
+        key
+    }
+
+    async fn update(&mut self, channel_id: ChannelId, key: DefaultKey, reminder: Reminder) {
+        let content = serde_json::to_string(&reminder).expect("Error serializing reminder");
+        if let Some(slot) = self
+            .data
+            .get_mut(&channel_id)
+            .and_then(|cd| cd.reminders.get_mut(key))
+        {
+            *slot = reminder;
+            self.execute(
+                "UPDATE reminders SET data = ?3 WHERE channel_id = ?1 AND key = ?2",
+                params![
+                    to_sql_id(channel_id.0),
+                    to_sql_id(key.data().as_ffi()),
+                    content
+                ],
+            );
+        }
+    }
+
+    async fn remove(&mut self, channel_id: ChannelId, key: DefaultKey) {
+        let removed = self
+            .data
+            .get_mut(&channel_id)
+            .and_then(|cd| cd.reminders.remove(key))
+            .is_some();
+        if !removed {
+            return;
+        }
+
+        // Removing bumps the slot's version to the next even one
+        let (idx, version) = split_key(key);
+        self.transaction(|tx| {
+            tx.execute(
+                "DELETE FROM reminders WHERE channel_id = ?1 AND key = ?2",
+                params![to_sql_id(channel_id.0), to_sql_id(key.data().as_ffi())],
+            )?;
+            tx.execute(
+                "INSERT OR REPLACE INTO vacant_slots (channel_id, idx, version) VALUES (?1, ?2, ?3)",
+                params![to_sql_id(channel_id.0), idx, version.wrapping_add(1)],
+            )?;
+
+            Ok(())
+        });
+    }
+
+    fn tz(&self, channel_id: ChannelId) -> Option<Tz> {
+        self.data.get(&channel_id).map(|cd| cd.tz)
+    }
+
+    async fn set_tz(&mut self, channel_id: ChannelId, tz_str: &str) -> Result<(), ParseError> {
+        let channel = self.data.entry(channel_id).or_default();
+        channel.set_tz(tz_str)?;
+        let tz = channel.tz.name();
+
+        self.ensure_channel(channel_id);
+        self.execute(
+            "INSERT OR REPLACE INTO timezones (channel_id, tz) VALUES (?1, ?2)",
+            params![to_sql_id(channel_id.0), tz],
+        );
+
+        Ok(())
+    }
+
+    async fn set_settings(&mut self, channel_id: ChannelId, settings: ChannelSettings) {
+        let content = serde_json::to_string(&settings).expect("Error serializing settings");
+        self.data.entry(channel_id).or_default().settings = settings;
+
+        self.ensure_channel(channel_id);
+        self.execute(
+            "UPDATE channels SET settings = ?2 WHERE id = ?1",
+            params![to_sql_id(channel_id.0), content],
+        );
+    }
+
+    async fn push_dead_letter(&mut self, channel_id: ChannelId, dead_letter: DeadLetter) {
+        self.data
+            .entry(channel_id)
+            .or_default()
+            .dead_letters
+            .push(dead_letter);
+        self.store_dead_letters(channel_id);
+    }
+
+    async fn take_dead_letter(
+        &mut self,
+        channel_id: ChannelId,
+        index: usize,
+    ) -> Option<DeadLetter> {
+        let dead_letters = &mut self.data.get_mut(&channel_id)?.dead_letters;
+        let dead_letter = (index < dead_letters.len()).then(|| dead_letters.remove(index))?;
+        self.store_dead_letters(channel_id);
+
+        Some(dead_letter)
+    }
+
+    fn user_tz(&self, user_id: UserId) -> Option<Tz> {
+        self.users.get(&user_id).copied()
+    }
+
+    async fn set_user_tz(
+        &mut self,
+        user_id: UserId,
+        tz_str: Option<&str>,
+    ) -> Result<(), ParseError> {
+        if let Some(tz_str) = tz_str {
+            let tz: Tz = tz_str.parse()?;
+            self.users.insert(user_id, tz);
+            self.execute(
+                "INSERT OR REPLACE INTO user_timezones (user_id, tz) VALUES (?1, ?2)",
+                params![to_sql_id(user_id.0), tz.name()],
+            );
+        } else {
+            self.users.remove(&user_id);
+            self.execute(
+                "DELETE FROM user_timezones WHERE user_id = ?1",
+                params![to_sql_id(user_id.0)],
+            );
+        }
+
+        Ok(())
+    }
+
+    fn channels(&self) -> Box<dyn Iterator<Item = (&ChannelId, &ChannelData)> + '_> {
+        Box::new(self.data.iter())
+    }
+
+    fn channel_data(&self, channel_id: ChannelId) -> Option<&ChannelData> {
+        self.data.get(&channel_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        db::TempDb,
+        reminder::{CatchUp, Mentions, ReminderType, SinkKind},
+    };
+    use chrono::NaiveDateTime;
+
+    fn reminder(msg: &str) -> Reminder {
+        Reminder::new(
+            ReminderType::Once(
+                NaiveDateTime::parse_from_str("2030-01-01 09:00", "%Y-%m-%d %H:%M").unwrap(),
+            ),
+            msg.to_string(),
+        )
+    }
+
+    fn dead_letter(msg: &str) -> DeadLetter {
+        DeadLetter {
+            msg: msg.to_string(),
+            author: Some(UserId(7)),
+            mentions: Mentions::default(),
+            embed: None,
+            target: None,
+            webhook: None,
+            sink: Some(SinkKind::Discord),
+            error: "Missing Access".to_string(),
+            failed_at: "2030-01-01T09:00:00Z".parse().unwrap(),
+        }
+    }
+
+    fn msgs(db: &SqliteDb, channel_id: ChannelId) -> Vec<(DefaultKey, String)> {
+        let mut msgs: Vec<_> = db
+            .channel_data(channel_id)
+            .map(|cd| {
+                cd.reminders
+                    .iter()
+                    .map(|(k, r)| (k, r.msg.clone()))
+                    .collect()
+            })
+            .unwrap_or_default();
+        msgs.sort();
+        msgs
+    }
+
+    #[tokio::test]
+    async fn everything_survives_reopening() {
+        let temp = TempDb::new("sqlite-round-trip");
+        let channel_id = ChannelId(1);
+
+        let mut db = SqliteDb::open(temp.path());
+        let kept = db.insert(channel_id, reminder("kept")).await;
+        let updated = db.insert(channel_id, reminder("before")).await;
+        let removed = db.insert(channel_id, reminder("removed")).await;
+        db.update(channel_id, updated, reminder("after")).await;
+        db.remove(channel_id, removed).await;
+        db.set_tz(channel_id, "Europe/Madrid").await.unwrap();
+        db.set_settings(
+            channel_id,
+            ChannelSettings {
+                catch_up: CatchUp::Skip,
+                snooze: Some(vec![10, 60]),
+                ..Default::default()
+            },
+        )
+        .await;
+        db.push_dead_letter(channel_id, dead_letter("first")).await;
+        db.push_dead_letter(channel_id, dead_letter("second")).await;
+        db.take_dead_letter(channel_id, 0).await.unwrap();
+        db.set_user_tz(UserId(7), Some("Asia/Tokyo")).await.unwrap();
+        db.set_user_tz(UserId(8), Some("Asia/Tokyo")).await.unwrap();
+        db.set_user_tz(UserId(8), None).await.unwrap();
+        let expected = msgs(&db, channel_id);
+        drop(db);
+
+        let db = SqliteDb::open(temp.path());
+        assert_eq!(msgs(&db, channel_id), expected);
+        assert_eq!(
+            expected.iter().map(|(k, _)| *k).collect::<Vec<_>>(),
+            [kept, updated]
+        );
+        assert_eq!(db.tz(channel_id), Some(chrono_tz::Europe::Madrid));
+        let channel = db.channel_data(channel_id).unwrap();
+        assert_eq!(channel.settings.catch_up, CatchUp::Skip);
+        assert_eq!(channel.settings.snooze_mins(), [10, 60]);
+        assert_eq!(channel.dead_letters.len(), 1);
+        assert_eq!(channel.dead_letters[0].msg, "second");
+        assert_eq!(channel.dead_letters[0].author, Some(UserId(7)));
+        assert_eq!(db.user_tz(UserId(7)), Some(chrono_tz::Asia::Tokyo));
+        assert_eq!(db.user_tz(UserId(8)), None);
+    }
+
+    #[tokio::test]
+    async fn removed_keys_are_not_reissued_after_reopening() {
+        let temp = TempDb::new("sqlite-removed-keys");
+        let channel_id = ChannelId(1);
+
+        let mut db = SqliteDb::open(temp.path());
+        let removed = db.insert(channel_id, reminder("removed")).await;
+        db.remove(channel_id, removed).await;
+        drop(db);
+
+        let mut db = SqliteDb::open(temp.path());
+        let reused = db.insert(channel_id, reminder("new")).await;
+        assert_ne!(reused, removed);
+        assert!(db
+            .channel_data(channel_id)
+            .unwrap()
+            .reminders
+            .get(removed)
+            .is_none());
+        drop(db);
+
+        // The slot is occupied again, so the vacancy must not outlive the insert
+        let db = SqliteDb::open(temp.path());
+        assert_eq!(msgs(&db, channel_id), [(reused, "new".to_string())]);
+        assert!(db
+            .channel_data(channel_id)
+            .unwrap()
+            .reminders
+            .get(removed)
+            .is_none());
+    }
+}
@@ -0,0 +1,130 @@
+use crate::manager::Manager;
+use hyper::{
+    header::CONTENT_TYPE,
+    service::{make_service_fn, service_fn},
+    Body, Method, Request, Response, Server, StatusCode,
+};
+use std::{convert::Infallible, net::SocketAddr};
+
+async fn respond(manager: Manager, req: Request<Body>) -> Result<Response<Body>, Infallible> {
+    if req.method() != Method::GET {
+        return Ok(status(StatusCode::METHOD_NOT_ALLOWED));
+    }
+
+    let token = req
+        .uri()
+        .path()
+        .strip_prefix('/')
+        .and_then(|path| path.strip_suffix(".ics"))
+        .filter(|token| !token.is_empty());
+    let ics = match token {
+        Some(token) => match manager.feed_channel(token).await {
+            Some(channel_id) => manager.export(channel_id).await,
+            None => None,
+        },
+        None => None,
+    };
+
+    Ok(match ics {
+        Some(ics) => Response::builder()
+            .header(CONTENT_TYPE, "text/calendar; charset=utf-8")
+            .body(Body::from(ics))
+            .expect("Error building feed response"),
+        None => status(StatusCode::NOT_FOUND),
+    })
+}
+
+fn status(code: StatusCode) -> Response<Body> {
+    let mut response = Response::new(Body::empty());
+    *response.status_mut() = code;
+    response
+}
+
+/// Serves the calendar feed of every channel with a feed token at
+/// `/<token>.ics`, generated from the reminders at the time of the request
+pub async fn serve(addr: SocketAddr, manager: Manager) {
+    let make_service = make_service_fn(move |_| {
+        let manager = manager.clone();
+        async move { Ok::<_, Infallible>(service_fn(move |req| respond(manager.clone(), req))) }
+    });
+
+    match Server::try_bind(&addr) {
+        Ok(server) => {
+            println!("Serving calendar feeds on {addr}");
+            if let Err(why) = server.serve(make_service).await {
+                println!("Feed server error: {why:?}");
+            }
+        }
+        Err(why) => println!("Cannot start feed server on {addr}: {why:?}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        db::TempDb,
+        reminder::{Reminder, ReminderType},
+    };
+    use chrono::NaiveDateTime;
+    use serenity::model::id::ChannelId;
+
+    async fn request(manager: &Manager, method: Method, path: &str) -> Response<Body> {
+        let req = Request::builder()
+            .method(method)
+            .uri(path)
+            .body(Body::empty())
+            .unwrap();
+        respond(manager.clone(), req).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn serves_feeds_by_token() {
+        let db = TempDb::new("feed");
+        let manager = Manager::new(db.open().await);
+        let channel_id = ChannelId(1);
+        manager
+            .set_channel_tz(channel_id, "Europe/Madrid")
+            .await
+            .unwrap();
+        manager
+            .add_reminder(
+                channel_id,
+                Reminder::new(
+                    ReminderType::Once(
+                        NaiveDateTime::parse_from_str("2099-12-01 09:00", "%Y-%m-%d %H:%M")
+                            .unwrap(),
+                    ),
+                    "standup".to_string(),
+                ),
+            )
+            .await;
+        manager
+            .set_feed_token(channel_id, Some("secret".to_string()))
+            .await;
+
+        let response = request(&manager, Method::GET, "/secret.ics").await;
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers()[CONTENT_TYPE],
+            "text/calendar; charset=utf-8"
+        );
+        let body = hyper::body::to_bytes(response.into_body()).await.unwrap();
+        let ics = String::from_utf8(body.to_vec()).unwrap();
+        assert!(ics.starts_with("BEGIN:VCALENDAR"));
+        assert!(ics.contains("standup"));
+
+        for path in ["/other.ics", "/secre.ics", "/secret", "/.ics", "/"] {
+            let response = request(&manager, Method::GET, path).await;
+            assert_eq!(response.status(), StatusCode::NOT_FOUND, "{path}");
+        }
+        for method in [Method::POST, Method::PUT, Method::DELETE] {
+            let response = request(&manager, method, "/secret.ics").await;
+            assert_eq!(response.status(), StatusCode::METHOD_NOT_ALLOWED);
+        }
+
+        manager.set_feed_token(channel_id, None).await;
+        let response = request(&manager, Method::GET, "/secret.ics").await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+}
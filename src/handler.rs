@@ -1,17 +1,24 @@
 use crate::{
-    commands::{Command, Menu, RemindIn, RemindMe, RemindOnce, Tz},
-    manager::Manager,
-    reminder::{Reminder, ReminderType},
+    commands::{
+        AdminRole, CatchUp, Command, DeadLetters, Export, Feed, Import, Menu, MyTz, Pause,
+        RemindIn, RemindMe, RemindOnce, RemindRule, RemindWhen, Resume, Skip, Snooze, Tz,
+    },
+    db::Backend,
+    manager::{self, Manager},
     reminder_menu::ReminderMenu,
+    snooze,
 };
-use chrono::{Duration, Utc};
 use serenity::{
     async_trait,
     model::{
-        gateway::Ready,
-        interactions::{
-            application_command::ApplicationCommand, Interaction, InteractionResponseType,
+        application::{
+            command::Command as ApplicationCommand,
+            interaction::{
+                message_component::MessageComponentInteraction, Interaction,
+                InteractionResponseType,
+            },
         },
+        gateway::Ready,
     },
     prelude::*,
 };
@@ -23,10 +30,62 @@ pub struct Handler {
 }
 
 impl Handler {
-    pub async fn with_file(db_path: &str) -> Self {
+    pub fn manager(&self) -> Manager {
+        self.manager.clone()
+    }
+
+    // Stops a nagging reminder and marks its message as acknowledged
+    async fn done(&self, ctx: &Context, message: &MessageComponentInteraction, key: &str) {
+        let (channel_id, key) = manager::button_key(key, message.channel_id);
+        // This should never panic because the key is always the valid json string
+        // generated in remind_at
+        let key = key.expect("Error deserializing key");
+        self.manager.acknowledge(channel_id, key).await;
+
+        let content = format!(
+            "{}\n*(done, acknowledged by <@{}>)*",
+            message.message.content, message.user.id
+        );
+        if let Err(why) = message
+            .create_interaction_response(&ctx.http, move |response| {
+                response
+                    .kind(InteractionResponseType::UpdateMessage)
+                    .interaction_response_data(|message| {
+                        message
+                            .content(content)
+                            .allowed_mentions(|am| am.empty_parse())
+                            .components(|comps| comps)
+                    })
+            })
+            .await
+        {
+            println!("Cannot respond to component interaction: {why:#?}");
+        }
+    }
+
+    pub async fn with_db(backend: Backend, db_path: &str) -> Self {
         Self {
-            manager: Manager::with_file(db_path).await,
-            commands: vec![&Menu, &RemindIn, &RemindMe, &RemindOnce, &Tz],
+            manager: Manager::new(backend.open(db_path).await),
+            commands: vec![
+                &AdminRole,
+                &CatchUp,
+                &DeadLetters,
+                &Export,
+                &Feed,
+                &Import,
+                &Menu,
+                &MyTz,
+                &Pause,
+                &RemindIn,
+                &RemindMe,
+                &RemindOnce,
+                &RemindRule,
+                &RemindWhen,
+                &Resume,
+                &Skip,
+                &Snooze,
+                &Tz,
+            ],
         }
     }
 }
@@ -50,58 +109,51 @@ impl EventHandler for Handler {
                     })
                     .collect();
 
-                // TODO: Add tempremindme command that creates an action row with a button to
-                // stop the reminders
+                let handler_opt = self.commands.iter().find(|c| c.name() == command.data.name);
+
+                if let Some(c) = handler_opt {
+                    c.handle(Arc::clone(&ctx), &self.manager, &command, options)
+                        .await;
+                }
+            }
+            Interaction::Autocomplete(autocomplete) => {
                 let handler_opt = self
                     .commands
                     .iter()
-                    .filter(|c| c.name() == command.data.name)
-                    .next();
+                    .find(|c| c.name() == autocomplete.data.name);
 
                 if let Some(c) = handler_opt {
-                    c.handle(Arc::clone(&ctx), &self.manager, &command, options)
-                        .await
+                    c.autocomplete(Arc::clone(&ctx), &self.manager, &autocomplete)
+                        .await;
                 }
             }
             Interaction::MessageComponent(message) => {
-                let mut parts = message.data.custom_id.splitn(2, "-");
+                let mut parts = message.data.custom_id.splitn(2, '-');
                 if let Some(prefix) = parts.next() {
                     match prefix {
                         "menu" => {
-                            let mut menu =
-                                ReminderMenu::new(&self.manager, message.channel_id).await;
+                            let mut menu = ReminderMenu::new(
+                                &self.manager,
+                                message.channel_id,
+                                message.user.id,
+                            )
+                            .await;
                             menu.handle(Arc::clone(&ctx), &self.manager, &message).await;
                         }
-                        "postpone" => {
-                            let dt = parts.next().unwrap().parse().unwrap();
-                            let msg = message.message.content.clone();
-
-                            let reminder = Reminder {
-                                reminder_type: ReminderType::Once(
-                                    Utc::now().naive_utc() + Duration::minutes(dt),
-                                ),
-                                msg,
-                            };
-
-                            self.manager
-                                .add_reminder(Arc::clone(&ctx), message.channel_id, reminder)
-                                .await;
-
-                            if let Err(why) = message
-                                .create_interaction_response(&ctx.http, move |response| {
-                                    response
-                                        .kind(InteractionResponseType::UpdateMessage)
-                                        .interaction_response_data(|message| message)
-                                })
-                                .await
-                            {
-                                println!("Cannot respond to component interaction: {:#?}", why);
-                            }
+                        "postpone" | "snooze" => {
+                            snooze::handle(Arc::clone(&ctx), &self.manager, &message).await;
                         }
+                        "done" => self.done(&ctx, &message, parts.next().unwrap()).await,
                         _ => (),
                     }
                 }
             }
+            Interaction::ModalSubmit(submit) if submit.data.custom_id.starts_with("edit-") => {
+                ReminderMenu::handle_edit(Arc::clone(&ctx), &self.manager, &submit).await;
+            }
+            Interaction::ModalSubmit(submit) if submit.data.custom_id.starts_with("snooze-") => {
+                snooze::handle_custom(Arc::clone(&ctx), &self.manager, &submit).await;
+            }
             _ => (),
         }
     }
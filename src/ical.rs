@@ -0,0 +1,633 @@
+use crate::{
+    reminder::{ChannelData, Reminder, ReminderType},
+    rrule::RRule,
+    template,
+    time_parser::to_utc,
+};
+use chrono::{DateTime, Duration, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use cron::Schedule;
+use serenity::model::id::ChannelId;
+use slotmap::Key;
+use std::str::FromStr;
+
+const PRODID: &str = "-//metis//reminders//EN";
+const LOCAL_FORMAT: &str = "%Y%m%dT%H%M%S";
+const UTC_FORMAT: &str = "%Y%m%dT%H%M%SZ";
+// Schedules that cannot be written as an RRULE keep their cron expression here
+const CRON_PROPERTY: &str = "X-METIS-CRON";
+
+// All-day events are reminded of at this hour
+const ALL_DAY_HOUR: u32 = 9;
+
+const MONTHS: [&str; 12] = [
+    "JAN", "FEB", "MAR", "APR", "MAY", "JUN", "JUL", "AUG", "SEP", "OCT", "NOV", "DEC",
+];
+// In cron order, which starts on Sunday
+const WEEKDAYS: [(&str, &str); 7] = [
+    ("SUN", "SU"),
+    ("MON", "MO"),
+    ("TUE", "TU"),
+    ("WED", "WE"),
+    ("THU", "TH"),
+    ("FRI", "FR"),
+    ("SAT", "SA"),
+];
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+fn unescape(text: &str) -> String {
+    let mut unescaped = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n' | 'N') => unescaped.push('\n'),
+            Some(c) => unescaped.push(c),
+            None => (),
+        }
+    }
+    unescaped
+}
+
+// Content lines longer than 75 octets are folded onto lines starting with a space
+fn fold(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len());
+    let mut len = 0;
+    for c in line.chars() {
+        if len + c.len_utf8() > 75 {
+            folded.push_str("\r\n ");
+            len = 1;
+        }
+        folded.push(c);
+        len += c.len_utf8();
+    }
+    folded + "\r\n"
+}
+
+// Values of a cron field as numbers starting at `first`, names are looked up in
+// `names`. Steps cannot be listed without knowing the range, so they give None
+fn expand(field: &str, names: &[&str], first: u32) -> Option<Vec<u32>> {
+    let value = |v: &str| {
+        names
+            .iter()
+            .position(|name| v.eq_ignore_ascii_case(name))
+            .and_then(|i| u32::try_from(i).ok())
+            .map(|i| i + first)
+            .or_else(|| v.parse().ok())
+    };
+
+    let mut values = Vec::new();
+    for item in field.split(',') {
+        match item.split_once('-') {
+            Some((from, to)) => values.extend(value(from)?..=value(to)?),
+            None => values.push(value(item)?),
+        }
+    }
+    Some(values)
+}
+
+fn join(values: &[u32], name: impl Fn(u32) -> Option<String>) -> Option<String> {
+    Some(
+        values
+            .iter()
+            .map(|v| name(*v))
+            .collect::<Option<Vec<_>>>()?
+            .join(","),
+    )
+}
+
+/// Rule parts equivalent to a cron schedule firing at a single time of day,
+/// the time itself comes from DTSTART
+fn cron_to_rrule(sched: &Schedule) -> Option<String> {
+    let expr = sched.to_string();
+    let fields = expr.split_whitespace().collect::<Vec<_>>();
+    let ([sec, min, hour, dom, month, dow] | [sec, min, hour, dom, month, dow, "*"]) = fields[..]
+    else {
+        return None;
+    };
+    for single in [sec, min, hour] {
+        single.parse::<u32>().ok()?;
+    }
+    let any = |field: &str| field == "*" || field == "?";
+
+    let mut parts = Vec::new();
+    if !any(month) {
+        let months = expand(month, &MONTHS, 1)?;
+        parts.push(format!(
+            "BYMONTH={}",
+            join(&months, |m| Some(m.to_string()))?
+        ));
+    }
+    if !any(dom) {
+        let days = expand(dom, &[], 1)?;
+        parts.push(format!(
+            "BYMONTHDAY={}",
+            join(&days, |d| Some(d.to_string()))?
+        ));
+    }
+    if !any(dow) {
+        let names = WEEKDAYS.map(|(name, _)| name);
+        let days = expand(dow, &names, 1)?;
+        let byday = join(&days, |d| {
+            let i = usize::try_from(d.checked_sub(1)?).ok()?;
+            WEEKDAYS.get(i).map(|(_, day)| (*day).to_string())
+        })?;
+        parts.push(format!("BYDAY={byday}"));
+    }
+
+    let freq = if !any(dom) {
+        "MONTHLY"
+    } else if !any(dow) {
+        "WEEKLY"
+    } else {
+        "DAILY"
+    };
+    parts.insert(0, format!("FREQ={freq}"));
+
+    Some(parts.join(";"))
+}
+
+// Limits of a reminder as rule parts
+fn limits(reminder: &Reminder) -> String {
+    match (reminder.remaining(), reminder.ends_at) {
+        (Some(remaining), _) => format!(";COUNT={remaining}"),
+        (None, Some(end)) => format!(";UNTIL={}", end.format(UTC_FORMAT)),
+        (None, None) => String::new(),
+    }
+}
+
+fn event_lines(reminder: &Reminder, tz: Tz, now: DateTime<Utc>) -> Option<Vec<String>> {
+    let local = |t: DateTime<Utc>| t.with_timezone(&tz).format(LOCAL_FORMAT).to_string();
+
+    let mut lines = Vec::new();
+    match &reminder.reminder_type {
+        ReminderType::Once(datetime) => {
+            lines.push(format!("DTSTART:{}", datetime.format(UTC_FORMAT)));
+        }
+        ReminderType::Scheduled(sched) => {
+            let next = reminder.next_after(tz, now)?;
+            lines.push(format!("DTSTART;TZID={tz}:{}", local(next)));
+            match cron_to_rrule(sched) {
+                Some(rule) => lines.push(format!("RRULE:{rule}{}", limits(reminder))),
+                None => lines.push(format!("{CRON_PROPERTY}:{sched}")),
+            }
+        }
+        ReminderType::Recurring(rrule) => {
+            // Rules are stored in the channel's timezone without saying so
+            for line in rrule.to_string().lines() {
+                match line.split_once(':') {
+                    Some(("DTSTART", value)) => lines.push(format!("DTSTART;TZID={tz}:{value}")),
+                    Some(("EXDATE", values)) => {
+                        lines.extend(values.split(',').map(|value| {
+                            if value.contains('T') {
+                                format!("EXDATE;TZID={tz}:{value}")
+                            } else {
+                                format!("EXDATE;VALUE=DATE:{value}")
+                            }
+                        }));
+                    }
+                    _ => lines.push(line.to_string()),
+                }
+            }
+        }
+    }
+
+    Some(lines)
+}
+
+/// Builds a calendar with an event for every reminder of a channel, each with an
+/// alarm at the time it fires in the timezone given for it
+pub fn export(
+    channel_id: ChannelId,
+    channel: &ChannelData,
+    tz: impl Fn(&Reminder) -> Tz,
+    now: DateTime<Utc>,
+) -> String {
+    let mut ics =
+        fold("BEGIN:VCALENDAR") + &fold("VERSION:2.0") + &fold(&format!("PRODID:{PRODID}"));
+
+    for (key, reminder) in &channel.reminders {
+        let Some(lines) = event_lines(reminder, tz(reminder), now) else {
+            continue;
+        };
+        let summary = escape(&reminder.msg);
+
+        ics += &fold("BEGIN:VEVENT");
+        ics += &fold(&format!("UID:{channel_id}-{}@metis", key.data().as_ffi()));
+        ics += &fold(&format!("DTSTAMP:{}", now.format(UTC_FORMAT)));
+        ics += &fold(&format!("SUMMARY:{summary}"));
+        for line in lines {
+            ics += &fold(&line);
+        }
+        ics += &fold("BEGIN:VALARM");
+        ics += &fold("ACTION:DISPLAY");
+        ics += &fold(&format!("DESCRIPTION:{summary}"));
+        ics += &fold("TRIGGER:PT0S");
+        ics += &fold("END:VALARM");
+        ics += &fold("END:VEVENT");
+    }
+
+    ics + &fold("END:VCALENDAR")
+}
+
+#[derive(Debug)]
+struct Property {
+    name: String,
+    params: Vec<(String, String)>,
+    value: String,
+}
+
+impl Property {
+    fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    // Resolves DATE and DATE-TIME values, floating times are in the channel's
+    // timezone
+    fn time(&self, value: &str, tz: Tz) -> Option<DateTime<Utc>> {
+        if let Some(utc) = value.strip_suffix('Z') {
+            let datetime = NaiveDateTime::parse_from_str(utc, LOCAL_FORMAT).ok()?;
+            return Some(Utc.from_utc_datetime(&datetime));
+        }
+        let tz = match self.param("TZID") {
+            Some(tzid) => tzid.trim_matches('"').parse().ok()?,
+            None => tz,
+        };
+        let local = match NaiveDateTime::parse_from_str(value, LOCAL_FORMAT) {
+            Ok(local) => local,
+            Err(_) => NaiveDate::parse_from_str(value, "%Y%m%d")
+                .ok()?
+                .and_hms_opt(ALL_DAY_HOUR, 0, 0)?,
+        };
+        to_utc(tz, local)
+    }
+}
+
+fn parse_property(line: &str) -> Option<Property> {
+    // Parameter values may be quoted and contain colons
+    let mut quoted = false;
+    let colon = line.find(|c| {
+        if c == '"' {
+            quoted = !quoted;
+        }
+        c == ':' && !quoted
+    })?;
+    let (head, value) = (&line[..colon], &line[colon + 1..]);
+    let mut head = head.split(';');
+    let name = head.next()?.to_uppercase();
+    let params = head
+        .filter_map(|p| p.split_once('='))
+        .map(|(k, v)| (k.to_uppercase(), v.to_string()))
+        .collect();
+
+    Some(Property {
+        name,
+        params,
+        value: value.to_string(),
+    })
+}
+
+// Durations like -PT15M, -P1D or -P1DT2H
+fn parse_duration(value: &str) -> Option<Duration> {
+    let (sign, value) = match value.strip_prefix('-') {
+        Some(value) => (-1, value),
+        None => (1, value.strip_prefix('+').unwrap_or(value)),
+    };
+    let mut total = Duration::zero();
+    let mut amount = String::new();
+    let mut time = false;
+    for c in value.strip_prefix('P')?.chars() {
+        let unit = match c {
+            '0'..='9' => {
+                amount.push(c);
+                continue;
+            }
+            'T' => {
+                time = true;
+                continue;
+            }
+            'W' => Duration::weeks(1),
+            'D' => Duration::days(1),
+            'H' if time => Duration::hours(1),
+            'M' if time => Duration::minutes(1),
+            'S' if time => Duration::seconds(1),
+            _ => return None,
+        };
+        total = total.checked_add(&(unit * amount.parse::<i32>().ok()?))?;
+        amount.clear();
+    }
+
+    amount.is_empty().then_some(total * sign)
+}
+
+#[derive(Default)]
+struct Event {
+    properties: Vec<Property>,
+    alarms: Vec<Vec<Property>>,
+}
+
+impl Event {
+    fn get(&self, name: &str) -> Option<&Property> {
+        self.properties.iter().find(|p| p.name == name)
+    }
+
+    // When the first alarm goes off relative to the event's start
+    fn alarm(&self, start: DateTime<Utc>, tz: Tz) -> DateTime<Utc> {
+        self.alarms
+            .iter()
+            .filter_map(|alarm| alarm.iter().find(|p| p.name == "TRIGGER"))
+            .find_map(|trigger| match trigger.param("VALUE") {
+                Some("DATE-TIME") => trigger.time(&trigger.value, tz),
+                _ => parse_duration(&trigger.value).map(|offset| start + offset),
+            })
+            .unwrap_or(start)
+    }
+
+    fn reminder(&self, tz: Tz, now: DateTime<Utc>) -> Result<Reminder, String> {
+        let msg = self
+            .get("SUMMARY")
+            .or_else(|| self.get("DESCRIPTION"))
+            .map(|p| unescape(&p.value))
+            .filter(|msg| !msg.trim().is_empty())
+            .ok_or("event has no summary")?;
+        template::validate(&msg)?;
+        let dtstart = self.get("DTSTART").ok_or("event has no start")?;
+        let start = dtstart
+            .time(&dtstart.value, tz)
+            .ok_or("invalid start time")?;
+        let at = self.alarm(start, tz);
+        // Like with the commands, reminders that can never fire are not created
+        let reminder = |reminder_type| {
+            let reminder = Reminder::new(reminder_type, msg.clone());
+            match reminder.next_after(tz, now) {
+                Some(_) => Ok(reminder),
+                None => Err("the reminder would never be sent".to_string()),
+            }
+        };
+
+        if let Some(cron) = self.get(CRON_PROPERTY) {
+            let sched = Schedule::from_str(&cron.value).map_err(|_| "invalid cron expression")?;
+            return reminder(ReminderType::Scheduled(sched));
+        }
+
+        if let Some(rule) = self.get("RRULE") {
+            // Recurrence rules run in the channel's timezone, with the alarm moving
+            // every occurrence
+            let offset = at - start;
+            let local = |t: DateTime<Utc>| (t + offset).with_timezone(&tz).format(LOCAL_FORMAT);
+            let mut lines = vec![
+                format!("DTSTART:{}", local(start)),
+                format!("RRULE:{}", rule.value),
+            ];
+            for exdate in self.properties.iter().filter(|p| p.name == "EXDATE") {
+                for value in exdate.value.split(',') {
+                    // Dates exclude the whole day
+                    if exdate.param("VALUE") == Some("DATE") {
+                        lines.push(format!("EXDATE:{value}"));
+                        continue;
+                    }
+                    let time = exdate.time(value, tz).ok_or("invalid EXDATE")?;
+                    lines.push(format!("EXDATE:{}", local(time)));
+                }
+            }
+            let rrule = lines.join("\n").parse::<RRule>()?;
+            return reminder(ReminderType::Recurring(rrule));
+        }
+
+        if at <= now {
+            return Err("event is in the past".to_string());
+        }
+        reminder(ReminderType::Once(at.naive_utc()))
+    }
+}
+
+fn events(ics: &str) -> Vec<Event> {
+    // Unfolding joins continuation lines back to the line they belong to
+    let mut lines: Vec<String> = Vec::new();
+    for line in ics.lines() {
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(continued), Some(last)) => last.push_str(continued),
+            _ => lines.push(line.trim_end_matches('\r').to_string()),
+        }
+    }
+
+    let mut events = Vec::new();
+    let mut event: Option<Event> = None;
+    let mut alarm: Option<Vec<Property>> = None;
+    for property in lines.iter().filter_map(|l| parse_property(l)) {
+        let value = property.value.to_uppercase();
+        match (property.name.as_str(), value.as_str()) {
+            ("BEGIN", "VEVENT") => event = Some(Event::default()),
+            ("END", "VEVENT") => events.extend(event.take()),
+            ("BEGIN", "VALARM") => alarm = Some(Vec::new()),
+            ("END", "VALARM") => {
+                if let (Some(event), Some(alarm)) = (event.as_mut(), alarm.take()) {
+                    event.alarms.push(alarm);
+                }
+            }
+            _ => match (alarm.as_mut(), event.as_mut()) {
+                (Some(alarm), _) => alarm.push(property),
+                (None, Some(event)) => event.properties.push(property),
+                (None, None) => (),
+            },
+        }
+    }
+
+    events
+}
+
+/// Creates a reminder for every event in a calendar, firing when the event's
+/// first alarm does or at its start. Events that cannot be imported are returned
+/// with the reason
+pub fn import(ics: &str, tz: Tz, now: DateTime<Utc>) -> (Vec<Reminder>, Vec<String>) {
+    let mut reminders = Vec::new();
+    let mut skipped = Vec::new();
+    for event in events(ics) {
+        match event.reminder(tz, now) {
+            Ok(reminder) => reminders.push(reminder),
+            Err(why) => {
+                let summary = event
+                    .get("SUMMARY")
+                    .map_or(String::new(), |p| unescape(&p.value));
+                skipped.push(format!("{summary}: {why}"));
+            }
+        }
+    }
+
+    (reminders, skipped)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono_tz::Europe::Madrid;
+    use slotmap::SlotMap;
+
+    fn now() -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339("2026-03-25T09:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    fn channel(reminders: Vec<Reminder>) -> ChannelData {
+        let mut slots = SlotMap::new();
+        for reminder in reminders {
+            slots.insert(reminder);
+        }
+        ChannelData {
+            tz: Madrid,
+            reminders: slots,
+            ..ChannelData::default()
+        }
+    }
+
+    #[test]
+    fn translates_cron() {
+        let rule = |expr| cron_to_rrule(&Schedule::from_str(expr).unwrap());
+        assert_eq!(
+            rule("0 30 9 * * Mon-Fri *").as_deref(),
+            Some("FREQ=WEEKLY;BYDAY=MO,TU,WE,TH,FR")
+        );
+        assert_eq!(
+            rule("0 0 12 1,15 Jan,Jul ? *").as_deref(),
+            Some("FREQ=MONTHLY;BYMONTH=1,7;BYMONTHDAY=1,15")
+        );
+        assert_eq!(rule("0 0 8 * * * *").as_deref(), Some("FREQ=DAILY"));
+        assert_eq!(rule("0 */15 * * * * *"), None);
+        assert_eq!(rule("0 0 9,17 * * * *"), None);
+    }
+
+    #[test]
+    fn round_trips() {
+        let schedule = Schedule::from_str("0 30 9 * * Mon-Fri *").unwrap();
+        let every_minute = Schedule::from_str("0 * * * * * *").unwrap();
+        let rrule = "DTSTART:20260401T170000 FREQ=MONTHLY;BYDAY=-1FR EXDATE:20260424T170000"
+            .parse()
+            .unwrap();
+        let mut counted = Reminder::new(ReminderType::Scheduled(schedule), "Standup".into());
+        counted.count = Some(10);
+        counted.fired = 3;
+        let ics = export(
+            ChannelId(1),
+            &channel(vec![
+                counted,
+                Reminder::new(
+                    ReminderType::Once(
+                        NaiveDateTime::parse_from_str("20261201T130000", LOCAL_FORMAT).unwrap(),
+                    ),
+                    "Wrap presents, tags; and bows".into(),
+                ),
+                Reminder::new(ReminderType::Recurring(rrule), "Expenses".into()),
+                Reminder::new(ReminderType::Scheduled(every_minute), "Ping".into()),
+            ]),
+            |_| Madrid,
+            now(),
+        );
+        assert!(ics.contains("RRULE:FREQ=WEEKLY;BYDAY=MO,TU,WE,TH,FR;COUNT=7\r\n"));
+        assert!(ics.contains("SUMMARY:Wrap presents\\, tags\\; and bows\r\n"));
+        assert!(ics.contains("DTSTART;TZID=Europe/Madrid:20260401T170000\r\n"));
+        assert!(ics.contains("X-METIS-CRON:0 * * * * * *\r\n"));
+        assert!(ics.lines().all(|l| l.trim_end_matches('\r').len() <= 75));
+
+        let (reminders, skipped) = import(&ics, Madrid, now());
+        assert!(skipped.is_empty(), "{skipped:?}");
+        let msgs = reminders.iter().map(|r| r.msg.as_str()).collect::<Vec<_>>();
+        assert_eq!(reminders.len(), 4);
+        assert!(msgs.contains(&"Wrap presents, tags; and bows"));
+        for reminder in reminders {
+            let next = reminder.next_after(Madrid, now()).unwrap();
+            match reminder.msg.as_str() {
+                "Expenses" => assert_eq!(next.to_rfc3339(), "2026-05-29T15:00:00+00:00"),
+                "Wrap presents, tags; and bows" => {
+                    assert_eq!(next.to_rfc3339(), "2026-12-01T13:00:00+00:00");
+                }
+                "Standup" => assert_eq!(next.to_rfc3339(), "2026-03-26T08:30:00+00:00"),
+                _ => assert!(matches!(reminder.reminder_type, ReminderType::Scheduled(_))),
+            }
+        }
+    }
+
+    #[test]
+    fn skips_events_that_cannot_be_reminded_of() {
+        let ics = "BEGIN:VCALENDAR\r\n\
+            BEGIN:VEVENT\r\n\
+            SUMMARY:Launch in {countdown:bad} days\r\n\
+            DTSTART:20260410T100000Z\r\n\
+            END:VEVENT\r\n\
+            BEGIN:VEVENT\r\n\
+            SUMMARY:Old standup\r\n\
+            DTSTART:20250101T090000Z\r\n\
+            RRULE:FREQ=DAILY;UNTIL=20250201T000000Z\r\n\
+            END:VEVENT\r\n\
+            BEGIN:VEVENT\r\n\
+            SUMMARY:Done retro\r\n\
+            DTSTART:20250101T090000Z\r\n\
+            RRULE:FREQ=WEEKLY;COUNT=3\r\n\
+            END:VEVENT\r\n\
+            BEGIN:VEVENT\r\n\
+            SUMMARY:Launch\r\n\
+            DTSTART:20260410T100000Z\r\n\
+            END:VEVENT\r\n\
+            END:VCALENDAR\r\n";
+
+        let (reminders, skipped) = import(ics, Madrid, now());
+        assert_eq!(reminders.len(), 1);
+        assert_eq!(reminders[0].msg, "Launch");
+        assert_eq!(skipped.len(), 3, "{skipped:?}");
+        assert!(skipped[0].starts_with("Launch in {countdown:bad} days: "));
+        assert_eq!(skipped[1], "Old standup: the reminder would never be sent");
+        assert_eq!(skipped[2], "Done retro: the reminder would never be sent");
+    }
+
+    #[test]
+    fn imports_alarms_and_foreign_timezones() {
+        let ics = "BEGIN:VCALENDAR\r\n\
+            BEGIN:VEVENT\r\n\
+            SUMMARY:Dentist\r\n\
+            DTSTART;TZID=America/New_York:20260410T100000\r\n\
+            BEGIN:VALARM\r\n\
+            TRIGGER:-PT1H30M\r\n\
+            END:VALARM\r\n\
+            END:VEVENT\r\n\
+            BEGIN:VEVENT\r\n\
+            SUMMARY:Old news\r\n\
+            DTSTART:20200101T100000Z\r\n\
+            END:VEVENT\r\n\
+            BEGIN:VEVENT\r\n\
+            SUMMARY:Holiday\r\n\
+            DTSTART;VALUE=DATE:20260501\r\n\
+            END:VEVENT\r\n\
+            BEGIN:VEVENT\r\n\
+            SUMMARY:Hourly\r\n\
+            DTSTART:20260401T100000\r\n\
+            RRULE:FREQ=HOURLY\r\n\
+            END:VEVENT\r\n\
+            END:VCALENDAR\r\n";
+        let (reminders, skipped) = import(ics, Madrid, now());
+
+        let times = reminders
+            .iter()
+            .map(|r| r.next_after(Madrid, now()).unwrap().to_rfc3339())
+            .collect::<Vec<_>>();
+        // 10:00 in New York is 14:00 UTC, the alarm goes off 90 minutes earlier
+        assert_eq!(
+            times,
+            ["2026-04-10T12:30:00+00:00", "2026-05-01T07:00:00+00:00"]
+        );
+        assert_eq!(skipped.len(), 2);
+        assert!(skipped[0].starts_with("Old news"));
+        assert!(skipped[1].contains("HOURLY"));
+    }
+}
@@ -1,12 +1,24 @@
 #![deny(clippy::pedantic)]
 
+mod api;
+// Commands share the imports of their parent module through `use super::*`
+#[allow(clippy::wildcard_imports)]
 mod commands;
 mod db;
+mod feed;
 mod handler;
+mod ical;
 mod manager;
 mod reminder;
 mod reminder_menu;
+mod rrule;
+mod scheduler;
+mod sink;
+mod snooze;
+mod template;
+mod time_parser;
 
+use db::Backend;
 use dotenv::dotenv;
 use handler::Handler;
 use serenity::prelude::*;
@@ -26,18 +38,35 @@ async fn main() {
         .parse()
         .expect("Application ID is not a number");
 
-    let mut client = Client::builder(token)
-        .event_handler(
-            Handler::with_file(
-                &env::var("DB_FILE").expect("Expected database file path in environment"),
-            )
-            .await,
-        )
+    let handler = Handler::with_db(
+        env::var("DB_BACKEND").map_or_else(
+            |_| Backend::default(),
+            |b| b.parse().expect("Invalid database backend"),
+        ),
+        &env::var("DB_FILE").expect("Expected database file path in environment"),
+    )
+    .await;
+
+    if let Ok(addr) = env::var("FEED_ADDR") {
+        let addr = addr.parse().expect("Invalid feed server address");
+        tokio::spawn(feed::serve(addr, handler.manager()));
+    }
+
+    if let Ok(addr) = env::var("API_ADDR") {
+        let addr = addr.parse().expect("Invalid API server address");
+        let token = env::var("API_TOKEN").expect("Expected an API token in the environment");
+        // An empty token would let requests without one through
+        assert!(!token.trim().is_empty(), "API token cannot be empty");
+        tokio::spawn(api::serve(addr, token, handler.manager()));
+    }
+
+    let mut client = Client::builder(token, GatewayIntents::empty())
+        .event_handler(handler)
         .application_id(application_id)
         .await
         .expect("Error creating client");
 
     if let Err(why) = client.start().await {
-        println!("Client error: {:?}", why);
+        println!("Client error: {why:?}");
     }
 }
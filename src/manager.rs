@@ -1,67 +1,148 @@
 use crate::{
-    db::Db,
-    reminder::{ChannelData, Reminder, ReminderType},
+    db::Storage,
+    ical,
+    reminder::{
+        CatchUp, ChannelData, ChannelSettings, DeadLetter, Reminder, ReminderType, SinkKind,
+        Webhook, DEFAULT_SNOOZE,
+    },
+    scheduler::{ReminderId, Scheduler},
+    sink::{deliver, Delivery, DiscordSink, Retry, Sink, WebhookSink},
 };
-use chrono::{NaiveDateTime, Utc};
-use chrono_tz::{ParseError, Tz};
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use chrono_tz::{Etc::UTC, ParseError, Tz};
+use ring::constant_time;
 use serenity::{
-    model::{id::ChannelId, interactions::message_component::ButtonStyle},
+    model::{
+        guild::Member,
+        id::{ChannelId, RoleId, UserId},
+        Permissions,
+    },
     prelude::*,
 };
-use slotmap::DefaultKey;
-use std::sync::Arc;
-use tokio::{sync::RwLock, time::sleep};
-
-async fn wait_until(datetime: NaiveDateTime) {
-    // We ensure chrono::Duration::to_std cannot panic by checking that the number
-    // of seconds is positive. Additionally, a non-positive number of seconds means
-    // we don't have to sleep
-    let remaining = datetime.signed_duration_since(Utc::now().naive_utc());
-    if remaining.num_seconds() > 0 {
-        sleep(remaining.to_std().unwrap()).await;
+use slotmap::{DefaultKey, Key};
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
+use tokio::sync::RwLock;
+
+// Occurrences that are later than this when they fire were missed while the bot
+// was offline
+const MISSED_GRACE_SECS: i64 = 60;
+// Upper bound on the missed occurrences sent one by one, so a frequent schedule
+// after a long downtime cannot flood the channel
+const MAX_MISSED: usize = 100;
+
+// Moderators and members with the channel's admin role manage everyone's reminders
+fn is_admin(admin_role: Option<RoleId>, member: &Member) -> bool {
+    member.permissions.is_some_and(Permissions::manage_messages)
+        || admin_role.is_some_and(|role| member.roles.contains(&role))
+}
+
+/// Channel and key of the reminder the buttons of a message belong to, messages
+/// sent to a target name the channel their reminder is stored in after the key
+pub fn button_key(key: &str, sent_in: ChannelId) -> (ChannelId, Option<DefaultKey>) {
+    let (key, channel_id) = match key.split_once('@') {
+        Some((key, channel)) => (key, channel.parse().map_or(sent_in, ChannelId)),
+        None => (key, sent_in),
+    };
+
+    (channel_id, serde_json::from_str(key).ok())
+}
+
+// Footer of reminders sent as embeds, naming who scheduled them and when they are
+// sent next
+async fn embed_footer(ctx: &Context, author: Option<UserId>, next: Option<String>) -> String {
+    let name = match author {
+        Some(author) => author.to_user(ctx).await.ok().map(|user| user.name),
+        None => None,
+    };
+
+    match (name, next) {
+        (Some(name), Some(next)) => format!("Scheduled by {name}, next at {next}"),
+        (Some(name), None) => format!("Scheduled by {name}"),
+        (None, Some(next)) => format!("Next at {next}"),
+        (None, None) => String::new(),
     }
 }
 
-async fn remind_at(
-    db: Arc<RwLock<Db>>,
-    ctx: Arc<Context>,
-    channel_id: ChannelId,
-    datetime: NaiveDateTime,
-    key: DefaultKey,
-    msg: &str,
-) {
-    wait_until(datetime).await;
-
-    if db.read().await.has_reminder(channel_id, key) {
-        // TODO: Log when no permission to send message rather than panic
-
-        channel_id
-            .send_message(&ctx, |m| {
-                m.content(msg).components(|comps| {
-                    comps.create_action_row(|ar| {
-                        [5, 15, 30].into_iter().fold(ar, |ar, dt| {
-                            ar.create_button(|b| {
-                                b.style(ButtonStyle::Secondary)
-                                    .label(format!("+{} min", dt))
-                                    .custom_id(format!("postpone-{}", dt))
-                            })
-                        })
-                    })
-                })
-            })
-            .await
-            .expect("Error sending reminder");
+// A message to send for the numbered occurrence of a reminder at the given time
+type Message = (DateTime<Utc>, u32, String);
+
+// Messages to send for an occurrence that became due at the given time, along
+// with the last occurrence they cover and how many there were. Anything later
+// than the grace period was missed while we were offline, past the first
+// `MAX_MISSED` of those the rest are only counted up to the last one that passed
+fn due(
+    reminder: &Reminder,
+    tz: Tz,
+    policy: CatchUp,
+    at: DateTime<Utc>,
+    now: DateTime<Utc>,
+) -> (Vec<Message>, DateTime<Utc>, u32) {
+    let grace = Duration::seconds(MISSED_GRACE_SECS);
+    let mut times = vec![at];
+    let mut latest = at;
+    let mut occurrences = 1_u32;
+    if now - at > grace {
+        while let Some(next) = reminder.next_after(tz, latest) {
+            let used_up = reminder.remaining().is_some_and(|r| occurrences >= r);
+            let folding = times.len() >= MAX_MISSED;
+            if used_up || (folding && next > now) || (!folding && now - next <= grace) {
+                break;
+            }
+            if !folding {
+                times.push(next);
+            }
+            latest = next;
+            occurrences = occurrences.saturating_add(1);
+        }
     }
+
+    let message = |at: DateTime<Utc>, nth: u32| {
+        let occurrence = reminder.fired.saturating_add(nth);
+        (at, occurrence, reminder.render(tz, at, occurrence))
+    };
+    let each = || {
+        (1..)
+            .zip(&times)
+            .map(|(nth, at)| message(*at, nth))
+            .collect()
+    };
+    let msgs = match policy {
+        _ if now - at <= grace => each(),
+        CatchUp::Skip => vec![],
+        CatchUp::Summary => {
+            let (latest, occurrence, msg) = message(latest, occurrences);
+            vec![(
+                latest,
+                occurrence,
+                format!(
+                    "{msg}\n*(missed {occurrences} time{} since <t:{}:f>)*",
+                    if occurrences == 1 { "" } else { "s" },
+                    at.timestamp()
+                ),
+            )]
+        }
+        CatchUp::All => each(),
+    };
+
+    (msgs, latest, occurrences)
 }
 
+#[derive(Clone)]
 pub struct Manager {
-    db: Arc<RwLock<Db>>,
+    db: Arc<RwLock<Box<dyn Storage>>>,
+    scheduler: Arc<Scheduler>,
+    started: Arc<AtomicBool>,
 }
 
 impl Manager {
-    pub async fn with_file(db_path: &str) -> Self {
+    pub fn new(db: Box<dyn Storage>) -> Self {
         Self {
-            db: Arc::new(RwLock::new(Db::open(db_path).await)),
+            db: Arc::new(RwLock::new(db)),
+            scheduler: Arc::new(Scheduler::default()),
+            started: Arc::new(AtomicBool::new(false)),
         }
     }
 
@@ -70,87 +151,977 @@ impl Manager {
         channel_id: ChannelId,
         tz_str: &str,
     ) -> Result<(), ParseError> {
-        self.db.write().await.set_tz(channel_id, tz_str).await?;
+        let mut db = self.db.write().await;
+        db.set_tz(channel_id, tz_str).await?;
+
+        // Reminders that are already planned were planned in the old timezone
+        if let Some(cd) = db.channel_data(channel_id) {
+            for (key, reminder) in &cd.reminders {
+                if self.scheduler.planned((channel_id, key)).is_some() {
+                    let tz = db.reminder_tz(channel_id, reminder).unwrap_or(cd.tz);
+                    self.start_reminding(channel_id, tz, key, reminder);
+                }
+            }
+        }
 
         Ok(())
     }
 
-    pub async fn channel_tz(&self, channel_id: ChannelId) -> Option<Tz> {
-        self.db.read().await.tz(channel_id)
+    /// Sets the timezone a user's reminders are created and fire in, without one
+    /// the channel's timezone applies
+    pub async fn set_user_tz(
+        &self,
+        user_id: UserId,
+        tz_str: Option<&str>,
+    ) -> Result<(), ParseError> {
+        let mut db = self.db.write().await;
+        db.set_user_tz(user_id, tz_str).await?;
+
+        // The user's reminders that are already planned were planned in the old timezone
+        for (channel_id, tz, key, reminder) in db.iter() {
+            if reminder.author == Some(user_id)
+                && self.scheduler.planned((*channel_id, key)).is_some()
+            {
+                self.start_reminding(*channel_id, tz, key, reminder);
+            }
+        }
+
+        Ok(())
     }
 
-    pub async fn channel_data(&self, channel_id: ChannelId) -> Option<ChannelData> {
+    /// Timezone times given by a user in a channel are in: their own if they set
+    /// one, otherwise the channel's
+    pub async fn user_tz(&self, user_id: UserId, channel_id: ChannelId) -> Tz {
+        let db = self.db.read().await;
+        db.user_tz(user_id)
+            .or_else(|| db.tz(channel_id))
+            .unwrap_or(UTC)
+    }
+
+    /// Timezone a reminder fires in
+    pub async fn reminder_tz(&self, channel_id: ChannelId, reminder: &Reminder) -> Tz {
         self.db
             .read()
             .await
+            .reminder_tz(channel_id, reminder)
+            .unwrap_or(UTC)
+    }
+
+    async fn modify_settings(&self, channel_id: ChannelId, f: impl FnOnce(&mut ChannelSettings)) {
+        let mut db = self.db.write().await;
+        let mut settings = db
             .channel_data(channel_id)
-            .map(|cd| cd.clone())
+            .map(|cd| cd.settings.clone())
+            .unwrap_or_default();
+        f(&mut settings);
+        db.set_settings(channel_id, settings).await;
+    }
+
+    pub async fn set_channel_catch_up(&self, channel_id: ChannelId, catch_up: CatchUp) {
+        self.modify_settings(channel_id, |s| s.catch_up = catch_up)
+            .await;
+    }
+
+    /// Sets or revokes the token the channel's calendar feed is served under
+    pub async fn set_feed_token(&self, channel_id: ChannelId, token: Option<String>) {
+        self.modify_settings(channel_id, |s| s.feed_token = token)
+            .await;
+    }
+
+    /// Sets or removes the role whose members can change anyone's reminders
+    pub async fn set_admin_role(&self, channel_id: ChannelId, role: Option<RoleId>) {
+        self.modify_settings(channel_id, |s| s.admin_role = role)
+            .await;
+    }
+
+    /// Sets the snooze buttons of the channel's reminders, or goes back to the
+    /// default ones
+    pub async fn set_snooze(&self, channel_id: ChannelId, snooze: Option<Vec<u32>>) {
+        self.modify_settings(channel_id, |s| s.snooze = snooze)
+            .await;
     }
 
+    /// Checks that a user may edit, pause or delete a reminder: its author can, as
+    /// can members who manage messages or have the channel's reminder admin role.
+    /// Outside of servers there is nobody else to protect the reminder from
+    pub async fn authorize(
+        &self,
+        channel_id: ChannelId,
+        key: DefaultKey,
+        user_id: UserId,
+        member: Option<&Member>,
+    ) -> Result<(), &'static str> {
+        let db = self.db.read().await;
+        let (Some(cd), Some(member)) = (db.channel_data(channel_id), member) else {
+            return Ok(());
+        };
+
+        let allowed = cd
+            .reminders
+            .get(key)
+            .is_none_or(|r| r.author == Some(user_id))
+            || is_admin(cd.settings.admin_role, member);
+        if allowed {
+            Ok(())
+        } else {
+            Err("you can only change reminders you created")
+        }
+    }
+
+    /// Checks that a member may change settings affecting everyone in the channel,
+    /// outside of servers anyone may
+    pub async fn authorize_admin(
+        &self,
+        channel_id: ChannelId,
+        member: Option<&Member>,
+    ) -> Result<(), &'static str> {
+        let Some(member) = member else {
+            return Ok(());
+        };
+        let admin_role = self
+            .db
+            .read()
+            .await
+            .channel_data(channel_id)
+            .and_then(|cd| cd.settings.admin_role);
+        if is_admin(admin_role, member) {
+            Ok(())
+        } else {
+            Err("only moderators and the channel's admin role can do that")
+        }
+    }
+
+    /// Channel whose calendar feed is served under the given token
+    pub async fn feed_channel(&self, token: &str) -> Option<ChannelId> {
+        // Tokens are secrets, comparing them must not reveal how much of one matched
+        let matches = |expected: &str| {
+            constant_time::verify_slices_are_equal(expected.as_bytes(), token.as_bytes()).is_ok()
+        };
+
+        self.db
+            .read()
+            .await
+            .channels()
+            .find(|(_, cd)| cd.settings.feed_token.as_deref().is_some_and(matches))
+            .map(|(channel_id, _)| *channel_id)
+    }
+
+    /// Calendar with the channel's reminders, each in the timezone it fires in
+    pub async fn export(&self, channel_id: ChannelId) -> Option<String> {
+        let db = self.db.read().await;
+        let cd = db.channel_data(channel_id)?;
+
+        Some(ical::export(
+            channel_id,
+            cd,
+            |reminder| db.reminder_tz(channel_id, reminder).unwrap_or(cd.tz),
+            Utc::now(),
+        ))
+    }
+
+    pub async fn channel_data(&self, channel_id: ChannelId) -> Option<ChannelData> {
+        self.db.read().await.channel_data(channel_id).cloned()
+    }
+
+    // Plans the first time a reminder fires. Occurrences that passed since it last
+    // fired are planned right away so that the catch-up policy can deal with them.
+    // Returns false if the reminder will never fire again
     fn start_reminding(
         &self,
-        ctx: Arc<Context>,
         channel_id: ChannelId,
         tz: Tz,
         key: DefaultKey,
-        reminder: Reminder,
+        reminder: &Reminder,
+    ) -> bool {
+        if reminder.paused {
+            return true;
+        }
+
+        let next = match reminder.reminder_type {
+            // One-shot reminders are only kept after firing while they nag
+            ReminderType::Once(_) if reminder.fired > 0 => None,
+            ReminderType::Once(datetime) => Some(Utc.from_utc_datetime(&datetime)),
+            ReminderType::Scheduled(_) | ReminderType::Recurring(_) => {
+                reminder.next_after(tz, reminder.last_fired.unwrap_or_else(Utc::now))
+            }
+        };
+        let pending = reminder.nag.as_ref().and_then(|nag| nag.pending);
+        let next = next.into_iter().chain(pending).min();
+
+        if let Some(at) = next {
+            self.scheduler.schedule((channel_id, key), at);
+        }
+
+        next.is_some()
+    }
+
+    async fn fire(&self, ctx: &Arc<Context>, (channel_id, key): ReminderId, at: DateTime<Utc>) {
+        let (tz, policy, mut reminder) = {
+            let db = self.db.read().await;
+            let Some(cd) = db.channel_data(channel_id) else {
+                return;
+            };
+            let Some(reminder) = cd.reminders.get(key) else {
+                return;
+            };
+            (
+                db.reminder_tz(channel_id, reminder).unwrap_or(cd.tz),
+                reminder.catch_up.unwrap_or(cd.settings.catch_up),
+                reminder.clone(),
+            )
+        };
+
+        // Re-sends of a reminder nobody acknowledged yet are not occurrences
+        if reminder.nag.as_ref().and_then(|nag| nag.pending) == Some(at) {
+            self.nag(ctx, (channel_id, key), tz, reminder).await;
+            return;
+        }
+
+        let now = Utc::now();
+        let (msgs, latest, occurrences) = due(&reminder, tz, policy, at, now);
+
+        // A new occurrence replaces the one that was nagged about
+        if let Some(nag) = &mut reminder.nag {
+            nag.acknowledge();
+            if !msgs.is_empty() {
+                nag.sent(now);
+            }
+        }
+        self.send(ctx, (channel_id, key), tz, &reminder, msgs);
+
+        reminder.last_fired = Some(latest);
+        reminder.fired = reminder.fired.saturating_add(occurrences);
+        self.plan_next((channel_id, key), &reminder, latest).await;
+    }
+
+    // Sends the reminder's messages, sending is done in its own task so a slow
+    // request or retries do not hold up other reminders
+    fn send(
+        &self,
+        ctx: &Arc<Context>,
+        (channel_id, key): ReminderId,
+        tz: Tz,
+        reminder: &Reminder,
+        msgs: Vec<Message>,
     ) {
-        let db = Arc::clone(&self.db);
+        let Some(&(latest, ..)) = msgs.last() else {
+            return;
+        };
+        let send_ctx = Arc::clone(ctx);
+        let author = reminder.author;
+        let next = reminder.next_after(tz, latest).map(|next| {
+            next.with_timezone(&tz)
+                .format("%Y-%m-%d %H:%M %Z")
+                .to_string()
+        });
+        // This should never panic, any key should be stringifiable
+        let mut button_key = serde_json::to_string(&key).expect("Error serializing key");
+        if reminder.target.is_some() {
+            button_key = format!("{button_key}@{channel_id}");
+        }
+        let mut delivery = Delivery {
+            channel_id,
+            target: reminder.target,
+            reminder_id: key.data().as_ffi(),
+            key: button_key,
+            mentions: reminder.mentions.clone(),
+            nagging: reminder.nag.is_some(),
+            snooze: Vec::new(),
+            embed: reminder.embed.clone(),
+            footer: String::new(),
+            at: latest,
+            occurrence: 0,
+        };
+        // Reminders with a webhook are only sent to Discord if they ask for it
+        let mut sinks: Vec<(SinkKind, Box<dyn Sink>)> = Vec::new();
+        if let Some(webhook) = &reminder.webhook {
+            sinks.push((SinkKind::Webhook, Box::new(WebhookSink::new(webhook))));
+        }
+        if reminder
+            .webhook
+            .as_ref()
+            .is_none_or(|webhook| webhook.discord)
+        {
+            sinks.push((
+                SinkKind::Discord,
+                Box::new(DiscordSink::new(Arc::clone(ctx))),
+            ));
+        }
+        let webhook = reminder.webhook.clone();
+        let manager = self.clone();
         tokio::spawn(async move {
-            match reminder.reminder_type {
-                ReminderType::Scheduled(sched) => {
-                    for datetime in sched.upcoming(tz) {
-                        remind_at(
-                            Arc::clone(&db),
-                            Arc::clone(&ctx),
-                            channel_id,
-                            datetime.naive_utc(),
-                            key,
-                            &reminder.msg,
-                        )
-                        .await;
+            delivery.snooze = manager
+                .db
+                .read()
+                .await
+                .channel_data(channel_id)
+                .map_or(&DEFAULT_SNOOZE[..], |cd| cd.settings.snooze_mins())
+                .to_vec();
+            if delivery.embed.is_some() {
+                delivery.footer = embed_footer(&send_ctx, author, next).await;
+            }
+            for (at, occurrence, msg) in &msgs {
+                delivery.at = *at;
+                delivery.occurrence = *occurrence;
+                for (kind, sink) in &sinks {
+                    let sent = deliver(sink.as_ref(), &delivery, msg, Retry::default()).await;
+                    if let Err(error) = sent {
+                        println!("Cannot send reminder, giving up: {error}");
+                        let dead_letter = DeadLetter {
+                            msg: msg.clone(),
+                            author,
+                            mentions: delivery.mentions.clone(),
+                            embed: delivery.embed.clone(),
+                            target: delivery.target,
+                            webhook: webhook.clone(),
+                            sink: Some(*kind),
+                            error,
+                            failed_at: Utc::now(),
+                        };
+                        manager
+                            .db
+                            .write()
+                            .await
+                            .push_dead_letter(channel_id, dead_letter)
+                            .await;
                     }
                 }
-                ReminderType::Once(datetime) => {
-                    remind_at(
-                        Arc::clone(&db),
-                        Arc::clone(&ctx),
-                        channel_id,
-                        datetime,
-                        key,
-                        &reminder.msg,
-                    )
-                    .await;
-                }
             }
+        });
+    }
+
+    // Sends an unacknowledged reminder again
+    async fn nag(&self, ctx: &Arc<Context>, id: ReminderId, tz: Tz, mut reminder: Reminder) {
+        let now = Utc::now();
+        if let Some(nag) = &mut reminder.nag {
+            nag.sent(now);
+        }
+        let latest = reminder.last_fired.unwrap_or(now);
+        let msg = reminder.render(tz, latest, reminder.fired);
+        self.send(ctx, id, tz, &reminder, vec![(latest, reminder.fired, msg)]);
 
+        self.plan_next(id, &reminder, latest).await;
+    }
+
+    // Stores the progress of a reminder that fired and plans the next time it is
+    // due, or removes it if there is none. The reminder can be edited or removed
+    // while it is being sent, so only its progress is taken from the copy that
+    // fired and removed reminders stay removed
+    async fn plan_next(
+        &self,
+        (channel_id, key): ReminderId,
+        fired: &Reminder,
+        latest: DateTime<Utc>,
+    ) {
+        let mut db = self.db.write().await;
+        let Some(mut reminder) = db
+            .channel_data(channel_id)
+            .and_then(|cd| cd.reminders.get(key))
+            .cloned()
+        else {
+            return;
+        };
+        let Some(tz) = db.reminder_tz(channel_id, &reminder) else {
+            return;
+        };
+
+        // Edits plan the reminder from the time they were made
+        reminder.last_fired = reminder.last_fired.max(fired.last_fired);
+        reminder.fired = fired.fired;
+        if let (Some(nag), Some(fired_nag)) = (&mut reminder.nag, &fired.nag) {
+            nag.attempts = fired_nag.attempts;
+            nag.pending = fired_nag.pending;
+        }
+
+        let latest = reminder.last_fired.unwrap_or(latest);
+        match reminder.next_due(tz, latest) {
+            Some(_) if reminder.paused => db.update(channel_id, key, reminder).await,
+            Some(next) => {
+                self.scheduler.schedule((channel_id, key), next);
+                db.update(channel_id, key, reminder).await;
+            }
             // If there are no more reminders, the entry is removed
-            db.write().await.remove(channel_id, key).await;
-        });
+            None => db.remove(channel_id, key).await,
+        }
+    }
+
+    /// Stops a reminder from nagging until its next occurrence, reminders without
+    /// one are removed
+    pub async fn acknowledge(&self, channel_id: ChannelId, key: DefaultKey) {
+        let mut finished = false;
+        self.modify_reminder(channel_id, key, |r, tz| {
+            if let Some(nag) = &mut r.nag {
+                nag.acknowledge();
+            }
+            // Reminders are only kept after their last occurrence while they nag
+            let latest = r.last_fired.unwrap_or_else(Utc::now);
+            finished = r.fired > 0 && r.next_after(tz, latest).is_none();
+        })
+        .await;
+
+        if finished {
+            self.remove_reminder(channel_id, key).await;
+        }
     }
 
     pub async fn start_reminders(&self, ctx: Arc<Context>) {
-        self.db
+        // Ready is sent again after reconnecting, but everything is already running
+        if self.started.swap(true, Ordering::SeqCst) {
+            return;
+        }
+
+        let used_up = self
+            .db
             .read()
             .await
             .iter()
-            .for_each(|(c, t, k, r)| self.start_reminding(Arc::clone(&ctx), *c, t, k, r.clone()));
+            .filter(|(c, t, k, r)| !self.start_reminding(**c, *t, *k, r))
+            .filter(|(_, _, _, r)| r.reminder_type.is_repeating())
+            .map(|(c, _, k, _)| (*c, k))
+            .collect::<Vec<_>>();
+        // Repeating reminders can run out while we were offline, e.g. by passing
+        // their end date
+        for (channel_id, key) in used_up {
+            self.db.write().await.remove(channel_id, key).await;
+        }
+
+        let manager = self.clone();
+        tokio::spawn(async move {
+            loop {
+                let (id, at) = manager.scheduler.next_due().await;
+                manager.fire(&ctx, id, at).await;
+            }
+        });
+    }
+
+    /// Time a reminder is planned to fire next, if any
+    pub fn planned(&self, channel_id: ChannelId, key: DefaultKey) -> Option<DateTime<Utc>> {
+        self.scheduler.planned((channel_id, key))
+    }
+
+    pub async fn add_reminder(&self, channel_id: ChannelId, reminder: Reminder) -> DefaultKey {
+        let mut db = self.db.write().await;
+        let key = db.insert(channel_id, reminder.clone()).await;
+        // This value is guaranteed to exist because at the very least we just inserted
+        // a key into this entry
+        let tz = db.reminder_tz(channel_id, &reminder).unwrap();
+        self.start_reminding(channel_id, tz, key, &reminder);
+
+        key
     }
 
-    pub async fn add_reminder(&self, ctx: Arc<Context>, channel_id: ChannelId, reminder: Reminder) {
-        let key = self
+    // Applies a change to a stored reminder and plans it again, returns the
+    // changed reminder or None if it does not exist
+    async fn modify_reminder(
+        &self,
+        channel_id: ChannelId,
+        key: DefaultKey,
+        f: impl FnOnce(&mut Reminder, Tz) + Send,
+    ) -> Option<Reminder> {
+        let mut db = self.db.write().await;
+        let mut reminder = db.channel_data(channel_id)?.reminders.get(key)?.clone();
+        let tz = db.reminder_tz(channel_id, &reminder)?;
+        f(&mut reminder, tz);
+        db.update(channel_id, key, reminder.clone()).await;
+
+        self.scheduler.cancel((channel_id, key));
+        self.start_reminding(channel_id, tz, key, &reminder);
+
+        Some(reminder)
+    }
+
+    /// Replaces a reminder and plans it again from now
+    pub async fn edit_reminder(&self, channel_id: ChannelId, key: DefaultKey, reminder: Reminder) {
+        self.modify_reminder(channel_id, key, |r, _| {
+            *r = reminder;
+            // Occurrences of the new schedule before the edit are not missed ones
+            r.last_fired = Some(Utc::now());
+        })
+        .await;
+    }
+
+    /// Stops a repeating reminder from firing until it is resumed
+    pub async fn pause_reminder(
+        &self,
+        channel_id: ChannelId,
+        key: DefaultKey,
+    ) -> Result<(), &'static str> {
+        self.modify_recurring(channel_id, key, |r, _| r.paused = true)
+            .await
+    }
+
+    /// Lets a paused reminder fire again, occurrences while it was paused are not
+    /// caught up on
+    pub async fn resume_reminder(
+        &self,
+        channel_id: ChannelId,
+        key: DefaultKey,
+    ) -> Result<(), &'static str> {
+        self.modify_recurring(channel_id, key, |r, _| {
+            r.paused = false;
+            r.last_fired = Some(Utc::now());
+        })
+        .await
+    }
+
+    /// Skips the next occurrence of a repeating reminder
+    pub async fn skip_reminder(
+        &self,
+        channel_id: ChannelId,
+        key: DefaultKey,
+    ) -> Result<(), &'static str> {
+        self.modify_recurring(channel_id, key, |r, tz| {
+            let after = r
+                .last_fired
+                .map_or_else(Utc::now, |last| last.max(Utc::now()));
+            r.skip_until = r.next_after(tz, after).or(r.skip_until);
+        })
+        .await
+    }
+
+    async fn modify_recurring(
+        &self,
+        channel_id: ChannelId,
+        key: DefaultKey,
+        f: impl FnOnce(&mut Reminder, Tz) + Send,
+    ) -> Result<(), &'static str> {
+        let is_recurring = self
+            .db
+            .read()
+            .await
+            .channel_data(channel_id)
+            .and_then(|cd| cd.reminders.get(key))
+            .map(|r| r.reminder_type.is_repeating())
+            .ok_or("reminder no longer exists")?;
+        if !is_recurring {
+            return Err("only repeating reminders can be paused or skipped");
+        }
+
+        self.modify_reminder(channel_id, key, f)
+            .await
+            .map(|_| ())
+            .ok_or("reminder no longer exists")
+    }
+
+    /// Sends a dead letter again as a new one-shot reminder, only to the sink it
+    /// could not be delivered to
+    pub async fn requeue_dead_letter(&self, channel_id: ChannelId, index: usize) -> bool {
+        let dead_letter = self
             .db
             .write()
             .await
-            .insert(channel_id, reminder.clone())
+            .take_dead_letter(channel_id, index)
             .await;
-        // This value is guaranteed to exist because at the very least we just inserted
-        // a key into this entry
-        let tz = self.channel_tz(channel_id).await.unwrap();
-        self.start_reminding(ctx, channel_id, tz, key, reminder);
+
+        if let Some(dead_letter) = dead_letter {
+            let webhook = match dead_letter.sink {
+                Some(SinkKind::Discord) => None,
+                Some(SinkKind::Webhook) => dead_letter.webhook.map(|webhook| Webhook {
+                    discord: false,
+                    ..webhook
+                }),
+                None => dead_letter.webhook,
+            };
+            self.add_reminder(
+                channel_id,
+                Reminder {
+                    author: dead_letter.author,
+                    mentions: dead_letter.mentions,
+                    embed: dead_letter.embed,
+                    target: dead_letter.target,
+                    webhook,
+                    ..Reminder::new(ReminderType::Once(Utc::now().naive_utc()), dead_letter.msg)
+                },
+            )
+            .await;
+            true
+        } else {
+            false
+        }
     }
 
     pub async fn remove_reminder(&self, channel_id: ChannelId, key: DefaultKey) {
+        self.scheduler.cancel((channel_id, key));
         self.db.write().await.remove(channel_id, key).await;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        db::TempDb,
+        reminder::{Mentions, Nag},
+    };
+    use chrono::Timelike;
+    use cron::Schedule;
+    use slotmap::SlotMap;
+    use std::str::FromStr;
+
+    #[test]
+    fn button_keys_name_their_channel() {
+        let mut keys = SlotMap::new();
+        let key: DefaultKey = keys.insert(());
+        let json = serde_json::to_string(&key).unwrap();
+
+        assert_eq!(button_key(&json, ChannelId(1)), (ChannelId(1), Some(key)));
+        assert_eq!(
+            button_key(&format!("{json}@2"), ChannelId(1)),
+            (ChannelId(2), Some(key))
+        );
+        assert_eq!(button_key("gone", ChannelId(1)), (ChannelId(1), None));
+    }
+
+    #[test]
+    fn caught_up_occurrences_keep_their_own_placeholders() {
+        let reminder = Reminder {
+            fired: 2,
+            ..Reminder::new(
+                ReminderType::Scheduled(Schedule::from_str("0 0 * * * * *").unwrap()),
+                "#{occurrence} at {time}".to_string(),
+            )
+        };
+        let at = "2026-12-01T09:00:00Z".parse().unwrap();
+        let now = at + Duration::minutes(150);
+
+        let (msgs, latest, occurrences) = due(&reminder, Tz::UTC, CatchUp::All, at, now);
+        assert_eq!(
+            msgs,
+            vec![
+                (at, 3, "#3 at 09:00".to_string()),
+                (at + Duration::hours(1), 4, "#4 at 10:00".to_string()),
+                (at + Duration::hours(2), 5, "#5 at 11:00".to_string()),
+            ]
+        );
+        assert_eq!((latest, occurrences), (at + Duration::hours(2), 3));
+
+        let (msgs, ..) = due(&reminder, Tz::UTC, CatchUp::Summary, at, now);
+        assert_eq!(msgs.len(), 1);
+        assert!(msgs[0].2.starts_with("#5 at 11:00\n*(missed 3 times"));
+        let (msgs, ..) = due(&reminder, Tz::UTC, CatchUp::Skip, at, now);
+        assert!(msgs.is_empty());
+        let (msgs, ..) = due(&reminder, Tz::UTC, CatchUp::All, at, at);
+        assert_eq!(msgs, vec![(at, 3, "#3 at 09:00".to_string())]);
+    }
+
+    #[test]
+    fn long_backlogs_are_folded() {
+        let reminder = Reminder::new(
+            ReminderType::Scheduled(Schedule::from_str("0 0 * * * * *").unwrap()),
+            "hourly".to_string(),
+        );
+        let at = "2026-01-01T00:00:00Z".parse().unwrap();
+        let now = at + Duration::hours(1500) + Duration::minutes(30);
+
+        let (msgs, latest, occurrences) = due(&reminder, Tz::UTC, CatchUp::All, at, now);
+        assert_eq!(msgs.len(), MAX_MISSED);
+        assert_eq!(occurrences, 1501);
+        assert_eq!(latest, at + Duration::hours(1500));
+        assert!(reminder.next_after(Tz::UTC, latest).unwrap() > now);
+
+        let (msgs, ..) = due(&reminder, Tz::UTC, CatchUp::Summary, at, now);
+        assert_eq!(msgs.len(), 1);
+        assert_eq!(msgs[0].0, latest);
+        assert_eq!(msgs[0].1, 1501);
+        assert!(msgs[0].2.contains("missed 1501 times"));
+
+        // Folding stops where the count runs out
+        let limited = Reminder {
+            count: Some(1200),
+            ..reminder
+        };
+        let (_, latest, occurrences) = due(&limited, Tz::UTC, CatchUp::All, at, now);
+        assert_eq!(occurrences, 1200);
+        assert_eq!(latest, at + Duration::hours(1199));
+    }
+
+    #[tokio::test]
+    async fn tz_change_moves_planned_reminders() {
+        let db = TempDb::new("tz");
+        let manager = Manager::new(db.open().await);
+        let channel_id = ChannelId(1);
+
+        let key = manager
+            .add_reminder(
+                channel_id,
+                Reminder::new(
+                    ReminderType::Scheduled(Schedule::from_str("0 0 9 * * * *").unwrap()),
+                    "standup".to_string(),
+                ),
+            )
+            .await;
+        let before = manager.scheduler.planned((channel_id, key)).unwrap();
+        assert_eq!(before.hour(), 9);
+
+        manager
+            .set_channel_tz(channel_id, "Asia/Tokyo")
+            .await
+            .unwrap();
+        let after = manager.scheduler.planned((channel_id, key)).unwrap();
+        assert_ne!(before, after);
+        assert_eq!(after.with_timezone(&chrono_tz::Asia::Tokyo).hour(), 9);
+        assert_eq!(after.hour(), 0);
+        assert!(after > Utc::now() && after - Utc::now() <= chrono::Duration::days(1));
+    }
+
+    #[tokio::test]
+    async fn changes_while_sending_are_kept() {
+        let db = TempDb::new("plan-next");
+        let manager = Manager::new(db.open().await);
+        let channel_id = ChannelId(1);
+        let standup = || {
+            Reminder::new(
+                ReminderType::Scheduled(Schedule::from_str("0 0 9 * * * *").unwrap()),
+                "standup".to_string(),
+            )
+        };
+
+        // Removed between becoming due and being planned again
+        let key = manager.add_reminder(channel_id, standup()).await;
+        let at = manager.planned(channel_id, key).unwrap();
+        let mut fired = standup();
+        let (_, latest, occurrences) = due(&fired, Tz::UTC, CatchUp::All, at, at);
+        fired.last_fired = Some(latest);
+        fired.fired = occurrences;
+        manager.remove_reminder(channel_id, key).await;
+        manager.plan_next((channel_id, key), &fired, latest).await;
+        assert!(manager.planned(channel_id, key).is_none());
+        assert!(manager
+            .channel_data(channel_id)
+            .await
+            .unwrap()
+            .reminders
+            .get(key)
+            .is_none());
+
+        // Edited between becoming due and being planned again
+        let key = manager.add_reminder(channel_id, standup()).await;
+        manager
+            .edit_reminder(
+                channel_id,
+                key,
+                Reminder {
+                    paused: true,
+                    ..Reminder::new(
+                        ReminderType::Scheduled(Schedule::from_str("0 0 18 * * * *").unwrap()),
+                        "retro".to_string(),
+                    )
+                },
+            )
+            .await;
+        manager.plan_next((channel_id, key), &fired, latest).await;
+        assert!(manager.planned(channel_id, key).is_none());
+        let cd = manager.channel_data(channel_id).await.unwrap();
+        let reminder = &cd.reminders[key];
+        assert_eq!(reminder.msg, "retro");
+        assert!(reminder.paused);
+        assert_eq!(reminder.fired, 1);
+        assert_eq!(reminder.last_fired, Some(latest));
+    }
+
+    #[tokio::test]
+    async fn only_authors_and_admins_manage_reminders() {
+        let db = TempDb::new("authorize");
+        let manager = Manager::new(db.open().await);
+        let channel_id = ChannelId(1);
+        let (author, other) = (UserId(2), UserId(3));
+        let member = |id: UserId, roles: &[u64], permissions: &str| -> Member {
+            serde_json::from_value(serde_json::json!({
+                "guild_id": "4",
+                "user": { "id": id.to_string(), "username": "user", "discriminator": "0001" },
+                "roles": roles.iter().map(ToString::to_string).collect::<Vec<_>>(),
+                "joined_at": null,
+                "deaf": false,
+                "mute": false,
+                "permissions": permissions,
+            }))
+            .unwrap()
+        };
+
+        let key = manager
+            .add_reminder(
+                channel_id,
+                Reminder {
+                    author: Some(author),
+                    ..Reminder::new(
+                        ReminderType::Scheduled(Schedule::from_str("0 0 9 * * * *").unwrap()),
+                        "standup".to_string(),
+                    )
+                },
+            )
+            .await;
+        let manage_messages = Permissions::MANAGE_MESSAGES.bits().to_string();
+        let (owner, stranger, moderator, admin) = (
+            member(author, &[], "0"),
+            member(other, &[6], "0"),
+            member(other, &[], &manage_messages),
+            member(other, &[5], "0"),
+        );
+        let authorize = |user_id, member| manager.authorize(channel_id, key, user_id, member);
+
+        assert!(authorize(author, Some(&owner)).await.is_ok());
+        assert!(authorize(other, Some(&stranger)).await.is_err());
+        assert!(authorize(other, Some(&moderator)).await.is_ok());
+        assert!(authorize(other, Some(&admin)).await.is_err());
+        // Outside of servers there are no members
+        assert!(authorize(other, None).await.is_ok());
+
+        let authorize_admin = |member| manager.authorize_admin(channel_id, member);
+        assert!(authorize_admin(Some(&owner)).await.is_err());
+        assert!(authorize_admin(Some(&moderator)).await.is_ok());
+        assert!(authorize_admin(None).await.is_ok());
+
+        manager.set_admin_role(channel_id, Some(RoleId(5))).await;
+        assert!(authorize(other, Some(&admin)).await.is_ok());
+        assert!(authorize(other, Some(&stranger)).await.is_err());
+        assert!(authorize_admin(Some(&admin)).await.is_ok());
+        assert!(authorize_admin(Some(&stranger)).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn nagging_survives_restarts_until_acknowledged() {
+        let db = TempDb::new("nag");
+        let channel_id = ChannelId(1);
+        let pending = Utc::now() + chrono::Duration::minutes(5);
+
+        // A one-shot reminder that fired and is waiting to be sent again
+        let mut nag = Nag::new(5, Some(3));
+        nag.sent(pending - chrono::Duration::minutes(5));
+        let mut reminder = Reminder::new(
+            ReminderType::Once((pending - chrono::Duration::minutes(5)).naive_utc()),
+            "water the plants".to_string(),
+        );
+        reminder.fired = 1;
+        reminder.nag = Some(nag);
+
+        let key = {
+            let manager = Manager::new(db.open().await);
+            manager.add_reminder(channel_id, reminder).await
+        };
+        let manager = Manager::new(db.open().await);
+        let cd = manager.channel_data(channel_id).await.unwrap();
+        let reminder = &cd.reminders[key];
+        assert!(manager.start_reminding(channel_id, chrono_tz::UTC, key, reminder));
+        assert_eq!(manager.scheduler.planned((channel_id, key)), Some(pending));
+
+        manager.acknowledge(channel_id, key).await;
+        assert_eq!(manager.scheduler.planned((channel_id, key)), None);
+        let cd = manager.channel_data(channel_id).await.unwrap();
+        assert!(cd.reminders.get(key).is_none());
+    }
+
+    #[test]
+    fn nagging_stops_after_max_attempts() {
+        let now = Utc::now();
+        let mut nag = Nag::new(10, Some(2));
+        nag.sent(now);
+        assert_eq!(nag.pending, Some(now + chrono::Duration::minutes(10)));
+        nag.sent(now);
+        assert_eq!(nag.pending, None);
+    }
+
+    #[tokio::test]
+    async fn reminders_fire_in_their_authors_tz() {
+        let db = TempDb::new("user-tz");
+        let manager = Manager::new(db.open().await);
+        let channel_id = ChannelId(1);
+        let author = UserId(2);
+        manager
+            .set_channel_tz(channel_id, "Asia/Tokyo")
+            .await
+            .unwrap();
+
+        let key = manager
+            .add_reminder(
+                channel_id,
+                Reminder {
+                    author: Some(author),
+                    ..Reminder::new(
+                        ReminderType::Scheduled(Schedule::from_str("0 0 9 * * * *").unwrap()),
+                        "standup".to_string(),
+                    )
+                },
+            )
+            .await;
+        let planned = manager.scheduler.planned((channel_id, key)).unwrap();
+        assert_eq!(planned.with_timezone(&chrono_tz::Asia::Tokyo).hour(), 9);
+
+        manager
+            .set_user_tz(author, Some("America/New_York"))
+            .await
+            .unwrap();
+        let planned = manager.scheduler.planned((channel_id, key)).unwrap();
+        assert_eq!(
+            planned.with_timezone(&chrono_tz::America::New_York).hour(),
+            9
+        );
+        assert_eq!(
+            manager.user_tz(author, channel_id).await,
+            chrono_tz::America::New_York
+        );
+        assert_eq!(
+            manager.user_tz(UserId(3), channel_id).await,
+            chrono_tz::Asia::Tokyo
+        );
+
+        // Without a timezone of their own the channel's applies again
+        manager.set_user_tz(author, None).await.unwrap();
+        let planned = manager.scheduler.planned((channel_id, key)).unwrap();
+        assert_eq!(planned.with_timezone(&chrono_tz::Asia::Tokyo).hour(), 9);
+    }
+
+    #[tokio::test]
+    async fn requeued_dead_letters_keep_their_author_and_sink() {
+        let db = TempDb::new("dead-letter");
+        let manager = Manager::new(db.open().await);
+        let channel_id = ChannelId(1);
+        let author = UserId(2);
+        let webhook = Webhook {
+            url: "https://example.com/hook".to_string(),
+            secret: "secret".to_string(),
+            discord: true,
+        };
+        for (sink, msg) in [
+            (SinkKind::Discord, "discord"),
+            (SinkKind::Webhook, "webhook"),
+        ] {
+            manager
+                .db
+                .write()
+                .await
+                .push_dead_letter(
+                    channel_id,
+                    DeadLetter {
+                        msg: msg.to_string(),
+                        author: Some(author),
+                        mentions: Mentions::default(),
+                        embed: None,
+                        target: None,
+                        webhook: Some(webhook.clone()),
+                        sink: Some(sink),
+                        error: format!("{msg}: failed"),
+                        failed_at: Utc::now(),
+                    },
+                )
+                .await;
+        }
+
+        assert!(manager.requeue_dead_letter(channel_id, 1).await);
+        assert!(manager.requeue_dead_letter(channel_id, 0).await);
+        assert!(!manager.requeue_dead_letter(channel_id, 0).await);
+        let cd = manager.channel_data(channel_id).await.unwrap();
+        assert!(cd.dead_letters.is_empty());
+        for reminder in cd.reminders.values() {
+            assert_eq!(reminder.author, Some(author));
+            let expected = match reminder.msg.as_str() {
+                "discord" => None,
+                _ => Some(Webhook {
+                    discord: false,
+                    ..webhook.clone()
+                }),
+            };
+            assert_eq!(reminder.webhook, expected);
+        }
+        assert_eq!(cd.reminders.len(), 2);
+    }
+}
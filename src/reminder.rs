@@ -1,9 +1,17 @@
-use chrono::NaiveDateTime;
+use crate::{
+    rrule::RRule,
+    template::{self, Vars},
+};
+use chrono::{DateTime, Duration, NaiveDateTime, TimeZone, Utc};
 use chrono_tz::{Etc::UTC, ParseError, Tz};
 use cron::Schedule;
 use serde::{Deserialize, Serialize};
+use serenity::{
+    builder::CreateEmbed,
+    model::id::{ChannelId, RoleId, UserId},
+};
 use slotmap::{DefaultKey, SlotMap};
-use std::{default::Default, str::FromStr};
+use std::{collections::HashMap, default::Default, fmt, str::FromStr};
 
 #[derive(Serialize, Deserialize)]
 #[serde(remote = "Schedule")]
@@ -18,23 +26,466 @@ impl From<ScheduleDef> for Schedule {
     }
 }
 
+/// Option names of the cron fields users fill in, in cron order after seconds
+pub const CRON_FIELDS: [&str; 6] = ["min", "hour", "dom", "month", "dow", "year"];
+
+/// Builds a schedule from cron fields keyed by [`CRON_FIELDS`], missing fields
+/// match any value
+pub fn parse_schedule(fields: &HashMap<String, String>) -> Result<Schedule, cron::error::Error> {
+    // Day of month and day of week are interrelated, therefore we must be careful
+    // when using them as fallback values
+    //
+    // If only one is set, we should set the other to "?"
+    let question_mark = "?".to_string();
+    let asterisk = "*".to_string();
+    let (dom, dow) = match (fields.get("dom"), fields.get("dow")) {
+        (Some(dom), Some(dow)) => (dom, dow),
+        (Some(dom), None) => (dom, &question_mark),
+        (None, Some(dow)) => (&question_mark, dow),
+        (None, None) => (&asterisk, &asterisk),
+    };
+
+    // We always put a 0 in the seconds slot since it is unlikely to be useful to
+    // the end user
+    Schedule::from_str(&format!(
+        "0 {} {} {} {} {} {}",
+        fields.get("min").unwrap_or(&asterisk),
+        fields.get("hour").unwrap_or(&asterisk),
+        dom,
+        fields.get("month").unwrap_or(&asterisk),
+        dow,
+        fields.get("year").unwrap_or(&asterisk),
+    ))
+}
+
+#[allow(clippy::large_enum_variant)]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum ReminderType {
     #[serde(with = "ScheduleDef")]
     Scheduled(Schedule),
     Once(NaiveDateTime),
+    Recurring(RRule),
+}
+
+impl ReminderType {
+    /// Whether the reminder fires more than once
+    pub fn is_repeating(&self) -> bool {
+        !matches!(self, ReminderType::Once(_))
+    }
+
+    /// Values of [`CRON_FIELDS`] describing when the reminder fires, one-shot
+    /// reminders are described by their date in the given timezone and
+    /// recurrence rules cannot be described
+    pub fn cron_fields(&self, tz: Tz) -> Option<Vec<String>> {
+        let fields = match self {
+            ReminderType::Scheduled(sched) => {
+                // Skip the seconds field, an expression without a year matches any
+                let mut fields = sched
+                    .to_string()
+                    .split_whitespace()
+                    .skip(1)
+                    .map(str::to_string)
+                    .collect::<Vec<_>>();
+                fields.resize(CRON_FIELDS.len(), "*".to_string());
+                fields
+            }
+            ReminderType::Once(datetime) => tz
+                .from_utc_datetime(datetime)
+                .format("%-M %-H %-d %-m ? %Y")
+                .to_string()
+                .split_whitespace()
+                .map(str::to_string)
+                .collect(),
+            ReminderType::Recurring(_) => return None,
+        };
+
+        Some(fields)
+    }
+
+    /// First time the reminder is due strictly after the given time
+    pub fn next_after(&self, tz: Tz, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        match self {
+            ReminderType::Scheduled(sched) => sched
+                .after(&after.with_timezone(&tz))
+                .next()
+                .map(|datetime| datetime.with_timezone(&Utc)),
+            ReminderType::Once(datetime) => {
+                let datetime = Utc.from_utc_datetime(datetime);
+                (datetime > after).then_some(datetime)
+            }
+            ReminderType::Recurring(rrule) => rrule.next_after(tz, after),
+        }
+    }
+}
+
+/// What to do with occurrences that passed while the bot was offline
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CatchUp {
+    /// Drop missed occurrences silently
+    Skip,
+    /// Send a single message noting how many occurrences were missed
+    #[default]
+    Summary,
+    /// Send every missed occurrence
+    All,
+}
+
+impl CatchUp {
+    pub const NAMES: [&'static str; 3] = ["skip", "summary", "all"];
+}
+
+impl FromStr for CatchUp {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "skip" => Ok(Self::Skip),
+            "summary" => Ok(Self::Summary),
+            "all" => Ok(Self::All),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Users and roles pinged when a reminder is sent
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Mentions {
+    #[serde(default)]
+    pub users: Vec<UserId>,
+    #[serde(default)]
+    pub roles: Vec<RoleId>,
+}
+
+impl Mentions {
+    pub fn is_empty(&self) -> bool {
+        self.users.is_empty() && self.roles.is_empty()
+    }
+
+    /// Splits the mentions put in front of a sent reminder from its message
+    pub fn split(content: &str) -> (Self, &str) {
+        let mut mentions = Self::default();
+        let mut rest = content;
+        while let Some((token, tail)) = rest.split_once(' ') {
+            let Some(id) = token.strip_prefix("<@").and_then(|t| t.strip_suffix('>')) else {
+                break;
+            };
+            match id.strip_prefix('&') {
+                Some(role) => match role.parse() {
+                    Ok(role) => mentions.roles.push(RoleId(role)),
+                    Err(_) => break,
+                },
+                None => match id.parse() {
+                    Ok(user) => mentions.users.push(UserId(user)),
+                    Err(_) => break,
+                },
+            }
+            rest = tail;
+        }
+
+        (mentions, rest)
+    }
+}
+
+impl fmt::Display for Mentions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let users = self.users.iter().map(|id| format!("<@{id}>"));
+        let roles = self.roles.iter().map(|id| format!("<@&{id}>"));
+
+        write!(f, "{}", users.chain(roles).collect::<Vec<_>>().join(" "))
+    }
+}
+
+/// Where a reminder is sent instead of the channel it was created in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Target {
+    /// A channel or thread
+    Channel(ChannelId),
+    /// Direct messages with a user
+    Dm(UserId),
+}
+
+impl fmt::Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Channel(channel_id) => write!(f, "<#{channel_id}>"),
+            Self::Dm(user_id) => write!(f, "DMs of <@{user_id}>"),
+        }
+    }
+}
+
+/// HTTP endpoint a reminder is posted to
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Webhook {
+    pub url: String,
+    /// Key the requests are signed with
+    pub secret: String,
+    /// Sends the reminder to Discord as well
+    #[serde(default)]
+    pub discord: bool,
+}
+
+/// Re-sending of a reminder every few minutes until someone acknowledges it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Nag {
+    /// Minutes between re-sends
+    pub every: u32,
+    /// Sends of an occurrence after which it is given up on, including the first
+    pub max_attempts: Option<u32>,
+    /// Sends of the current occurrence so far
+    #[serde(default)]
+    pub attempts: u32,
+    /// When the next re-send is due, None once acknowledged or given up on
+    #[serde(default)]
+    pub pending: Option<DateTime<Utc>>,
+}
+
+impl Nag {
+    pub fn new(every: u32, max_attempts: Option<u32>) -> Self {
+        Self {
+            every,
+            max_attempts,
+            attempts: 0,
+            pending: None,
+        }
+    }
+
+    /// Records a send and plans the next one, unless the attempts are used up
+    pub fn sent(&mut self, at: DateTime<Utc>) {
+        self.attempts = self.attempts.saturating_add(1);
+        self.pending = self
+            .max_attempts
+            .is_none_or(|max| self.attempts < max)
+            .then(|| at + Duration::minutes(self.every.into()));
+    }
+
+    pub fn acknowledge(&mut self) {
+        self.attempts = 0;
+        self.pending = None;
+    }
+}
+
+/// Look of a reminder sent as an embed rather than as plain text
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Embed {
+    pub title: Option<String>,
+    pub colour: Option<u32>,
+}
+
+impl Embed {
+    /// Parses a colour written in hex, e.g. `#ff8800`
+    pub fn parse_colour(colour: &str) -> Option<u32> {
+        let hex = colour.trim().trim_start_matches('#');
+        if hex.len() == 6 {
+            u32::from_str_radix(hex, 16).ok()
+        } else {
+            None
+        }
+    }
+
+    /// Fills in an embed showing a reminder message
+    pub fn render<'a>(
+        &self,
+        embed: &'a mut CreateEmbed,
+        msg: &str,
+        footer: &str,
+        timestamp: DateTime<Utc>,
+    ) -> &'a mut CreateEmbed {
+        embed.description(msg).timestamp(timestamp.to_rfc3339());
+        if let Some(title) = &self.title {
+            embed.title(title);
+        }
+        if let Some(colour) = self.colour {
+            embed.colour(colour);
+        }
+        if !footer.is_empty() {
+            embed.footer(|f| f.text(footer));
+        }
+
+        embed
+    }
 }
 
+#[allow(clippy::struct_field_names)]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Reminder {
     pub reminder_type: ReminderType,
     pub msg: String,
+    /// Overrides the channel's catch-up policy
+    #[serde(default)]
+    pub catch_up: Option<CatchUp>,
+    #[serde(default)]
+    pub last_fired: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub paused: bool,
+    /// Occurrences up to and including this time are not sent
+    #[serde(default)]
+    pub skip_until: Option<DateTime<Utc>>,
+    /// Number of occurrences after which the reminder is removed
+    #[serde(default)]
+    pub count: Option<u32>,
+    /// Occurrences that already happened, counted against `count`
+    #[serde(default)]
+    pub fired: u32,
+    /// Occurrences before this time are not sent
+    #[serde(default)]
+    pub starts_at: Option<DateTime<Utc>>,
+    /// Occurrences after this time are not sent
+    #[serde(default)]
+    pub ends_at: Option<DateTime<Utc>>,
+    /// User who created the reminder, it fires in their timezone if they set one
+    #[serde(default)]
+    pub author: Option<UserId>,
+    #[serde(default)]
+    pub mentions: Mentions,
+    #[serde(default)]
+    pub nag: Option<Nag>,
+    /// Reminder this one was snoozed from
+    #[serde(default)]
+    pub snoozed_from: Option<DefaultKey>,
+    /// Sends the reminder as an embed instead of plain text
+    #[serde(default)]
+    pub embed: Option<Embed>,
+    #[serde(default)]
+    pub target: Option<Target>,
+    #[serde(default)]
+    pub webhook: Option<Webhook>,
+}
+
+impl Reminder {
+    pub fn new(reminder_type: ReminderType, msg: String) -> Self {
+        Self {
+            reminder_type,
+            msg,
+            catch_up: None,
+            last_fired: None,
+            paused: false,
+            skip_until: None,
+            count: None,
+            fired: 0,
+            starts_at: None,
+            ends_at: None,
+            author: None,
+            mentions: Mentions::default(),
+            nag: None,
+            snoozed_from: None,
+            embed: None,
+            target: None,
+            webhook: None,
+        }
+    }
+
+    /// Occurrences left before the count is used up, if the reminder has one
+    pub fn remaining(&self) -> Option<u32> {
+        self.count.map(|count| count.saturating_sub(self.fired))
+    }
+
+    /// Next time the reminder has to be sent after the occurrence at the given
+    /// time: its next occurrence or its next re-send, whichever comes first
+    pub fn next_due(&self, tz: Tz, latest: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let pending = self.nag.as_ref().and_then(|nag| nag.pending);
+        self.next_after(tz, latest).into_iter().chain(pending).min()
+    }
+
+    /// Message sent for the numbered occurrence at the given time, with its
+    /// placeholders filled in
+    pub fn render(&self, tz: Tz, at: DateTime<Utc>, occurrence: u32) -> String {
+        template::render(
+            &self.msg,
+            &Vars {
+                at: at.with_timezone(&tz),
+                occurrence,
+                next: self.next_after(tz, at).map(|next| next.with_timezone(&tz)),
+            },
+        )
+    }
+
+    /// First time the reminder is due strictly after the given time, leaving out
+    /// skipped occurrences and the ones outside its limits
+    pub fn next_after(&self, tz: Tz, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        if self.remaining() == Some(0) {
+            return None;
+        }
+
+        let after = self.skip_until.map_or(after, |skip| skip.max(after));
+        // The start itself is a valid occurrence
+        let after = self
+            .starts_at
+            .map_or(after, |start| (start - Duration::nanoseconds(1)).max(after));
+        self.reminder_type
+            .next_after(tz, after)
+            .filter(|next| self.ends_at.is_none_or(|end| *next <= end))
+    }
+}
+
+/// Snooze buttons of channels that did not choose their own
+pub const DEFAULT_SNOOZE: [u32; 3] = [5, 15, 30];
+/// Snooze buttons fit in a row next to the custom snooze button
+pub const MAX_SNOOZE_BUTTONS: usize = 4;
+/// Reminders cannot be snoozed for longer than a week
+pub const MAX_SNOOZE_MINS: u32 = 7 * 24 * 60;
+
+/// Per-channel configuration, stored alongside the channel's reminders
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct ChannelSettings {
+    #[serde(default)]
+    pub catch_up: CatchUp,
+    /// Secret part of the URL the channel's calendar feed is served at
+    #[serde(default)]
+    pub feed_token: Option<String>,
+    /// Members with this role can change anyone's reminders
+    #[serde(default)]
+    pub admin_role: Option<RoleId>,
+    /// Minutes offered by the snooze buttons of sent reminders
+    #[serde(default)]
+    pub snooze: Option<Vec<u32>>,
+}
+
+impl ChannelSettings {
+    pub fn snooze_mins(&self) -> &[u32] {
+        self.snooze.as_deref().unwrap_or(&DEFAULT_SNOOZE)
+    }
+}
+
+/// Where a reminder's messages are delivered to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SinkKind {
+    Discord,
+    Webhook,
+}
+
+/// A reminder message that could not be delivered
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeadLetter {
+    pub msg: String,
+    /// Author of the reminder, who keeps owning it once it is requeued
+    #[serde(default)]
+    pub author: Option<UserId>,
+    #[serde(default)]
+    pub mentions: Mentions,
+    #[serde(default)]
+    pub embed: Option<Embed>,
+    #[serde(default)]
+    pub target: Option<Target>,
+    #[serde(default)]
+    pub webhook: Option<Webhook>,
+    /// Sink the message could not be delivered to, the only one it is sent to
+    /// again. Dead letters of older versions do not know theirs
+    #[serde(default)]
+    pub sink: Option<SinkKind>,
+    pub error: String,
+    pub failed_at: DateTime<Utc>,
 }
 
 #[derive(Clone, Serialize, Deserialize)]
 pub struct ChannelData {
     pub tz: Tz,
     pub reminders: SlotMap<DefaultKey, Reminder>,
+    #[serde(flatten)]
+    pub settings: ChannelSettings,
+    #[serde(default)]
+    pub dead_letters: Vec<DeadLetter>,
 }
 
 impl Default for ChannelData {
@@ -42,6 +493,8 @@ impl Default for ChannelData {
         Self {
             tz: UTC,
             reminders: SlotMap::new(),
+            settings: ChannelSettings::default(),
+            dead_letters: Vec::new(),
         }
     }
 }
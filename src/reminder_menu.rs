@@ -1,115 +1,394 @@
 use crate::{
     manager::Manager,
-    reminder::{ChannelData, Reminder, ReminderType},
+    reminder::{parse_schedule, ChannelData, Reminder, ReminderType, Target, CRON_FIELDS},
+    template,
 };
-use chrono::TimeZone;
+use chrono::{DateTime, Utc};
 use chrono_tz::Tz;
+use cron::Schedule;
 use serenity::{
-    builder::CreateInteractionResponseData,
+    builder::{CreateActionRow, CreateEmbed, CreateInteractionResponseData},
     model::{
-        id::ChannelId,
-        interactions::{
-            message_component::{ButtonStyle, ComponentType, MessageComponentInteraction},
-            InteractionResponseType,
+        application::{
+            component::{ActionRowComponent, ButtonStyle, ComponentType, InputTextStyle},
+            interaction::{
+                message_component::MessageComponentInteraction, modal::ModalSubmitInteraction,
+                InteractionResponseType,
+            },
         },
+        id::{ChannelId, UserId},
     },
     prelude::*,
 };
 use slotmap::DefaultKey;
 use std::{collections::HashMap, sync::Arc};
 
+// A reminder listed in the menu with the next time it is sent
+type Entry<'a> = (DefaultKey, &'a Reminder, Option<DateTime<Utc>>);
+
+// Discord allows at most 25 options in a select menu
+const PAGE_SIZE: usize = 25;
+// Discord rejects labels and descriptions of options longer than this
+const OPTION_LENGTH: usize = 100;
+
 pub struct ReminderMenu {
+    // Timezone of the user viewing the menu, times are shown and edited in it
     tz: Tz,
     reminders: HashMap<DefaultKey, Reminder>,
+    // Timezone each reminder fires in
+    zones: HashMap<DefaultKey, Tz>,
     selected: Option<String>,
+    page: usize,
 }
 
-fn limit_length(message: &str, len: usize) -> String {
-    if message.len() >= len {
-        message.get(..(len - 3)).unwrap().to_string() + "..."
+pub fn limit_length(message: &str, len: usize) -> String {
+    if message.chars().count() >= len {
+        message.chars().take(len - 3).collect::<String>() + "..."
     } else {
         message.to_string()
     }
 }
 
+// Builds a schedule from the space-separated cron fields typed into the modal
+fn parse_cron_fields(schedule: &str) -> Result<Schedule, &'static str> {
+    let values = schedule.split_whitespace().collect::<Vec<_>>();
+    if values.len() > CRON_FIELDS.len() {
+        return Err("too many schedule fields");
+    }
+    // Wildcards are left out so the same fallbacks as the commands apply
+    let fields = CRON_FIELDS
+        .iter()
+        .zip(values)
+        .filter(|(_, v)| *v != "*")
+        .map(|(k, v)| ((*k).to_string(), v.to_string()))
+        .collect();
+
+    parse_schedule(&fields).map_err(|_| "invalid cron expression")
+}
+
 impl ReminderMenu {
-    pub async fn new(manager: &Manager, channel_id: ChannelId) -> Self {
+    pub async fn new(manager: &Manager, channel_id: ChannelId, viewer: UserId) -> Self {
         let channel = manager
             .channel_data(channel_id)
             .await
             .unwrap_or_else(ChannelData::default);
-        let tz = channel.tz;
+        let tz = manager.user_tz(viewer, channel_id).await;
         let reminders = channel.reminders.into_iter().collect::<HashMap<_, _>>();
+        let mut zones = HashMap::new();
+        for (key, reminder) in &reminders {
+            zones.insert(*key, manager.reminder_tz(channel_id, reminder).await);
+        }
 
         Self {
             tz,
             reminders,
+            zones,
             selected: None,
+            page: 0,
+        }
+    }
+
+    // Reminders ordered by the next time they fire, the ones that will not fire
+    // again go last and ties are broken by key so that pages stay put
+    fn sorted(&self) -> Vec<Entry<'_>> {
+        let now = Utc::now();
+        let mut reminders = self
+            .reminders
+            .iter()
+            .map(|(k, r)| {
+                (
+                    *k,
+                    r,
+                    r.next_after(self.zone(*k), now).filter(|_| !r.paused),
+                )
+            })
+            .collect::<Vec<_>>();
+        reminders.sort_by_key(|(k, _, next)| (next.is_none(), *next, *k));
+
+        // Snoozes are listed right after the reminder they were snoozed from, unless
+        // that one is a snooze itself so that none of them are left out
+        let linked = |r: &Reminder| {
+            r.snoozed_from.filter(|from| {
+                self.reminders
+                    .get(from)
+                    .is_some_and(|original| original.snoozed_from.is_none())
+            })
+        };
+        let (snoozes, originals): (Vec<_>, Vec<_>) = reminders
+            .into_iter()
+            .partition(|(_, r, _)| linked(r).is_some());
+        originals
+            .into_iter()
+            .flat_map(|entry| {
+                let key = entry.0;
+                std::iter::once(entry).chain(
+                    snoozes
+                        .iter()
+                        .filter(move |(_, r, _)| linked(r) == Some(key))
+                        .copied(),
+                )
+            })
+            .collect()
+    }
+
+    // Message the reminder sends next, with its placeholders filled in
+    fn rendered(
+        &self,
+        key: DefaultKey,
+        reminder: &Reminder,
+        next: Option<DateTime<Utc>>,
+    ) -> String {
+        reminder.render(
+            self.zone(key),
+            next.unwrap_or_else(Utc::now),
+            reminder.fired.saturating_add(1),
+        )
+    }
+
+    fn zone(&self, key: DefaultKey) -> Tz {
+        self.zones.get(&key).copied().unwrap_or(self.tz)
+    }
+
+    fn pages(&self) -> usize {
+        self.reminders.len().div_ceil(PAGE_SIZE).max(1)
+    }
+
+    // The stored page may be past the end after reminders were removed
+    fn page(&self) -> usize {
+        self.page.min(self.pages() - 1)
+    }
+
+    // Moves to the previous or next page, staying within the existing ones
+    fn turn_page(&mut self, direction: &str) {
+        match direction {
+            "prev" => self.page = self.page().saturating_sub(1),
+            "next" => self.page = (self.page() + 1).min(self.pages() - 1),
+            _ => (),
+        }
+    }
+
+    // Selects a reminder and moves to the page it is on
+    fn select(&mut self, key_str: &str) {
+        if let Ok(key) = serde_json::from_str::<DefaultKey>(key_str) {
+            if let Some(pos) = self.sorted().iter().position(|(k, _, _)| *k == key) {
+                self.page = pos / PAGE_SIZE;
+            }
+        }
+        self.selected = Some(key_str.to_string());
+    }
+
+    // Next fire time, kind and limits of a reminder, e.g.
+    // "Tue, 1 Dec 2026 09:00:00 +0100 (Repeating, 3 of 10 remaining)"
+    fn describe(&self, reminder: &Reminder, next: Option<DateTime<Utc>>) -> String {
+        let kind = match &reminder.reminder_type {
+            ReminderType::Once(_) if reminder.snoozed_from.is_some() => "Snoozed",
+            ReminderType::Once(_) => "One-shot",
+            _ if reminder.paused => "Paused",
+            _ => "Repeating",
+        };
+        let mut info = vec![kind.to_string()];
+        if let (Some(remaining), Some(count)) = (reminder.remaining(), reminder.count) {
+            info.push(format!("{remaining} of {count} remaining"));
+        }
+        if let Some(nag) = &reminder.nag {
+            info.push(format!("nags every {} min", nag.every));
+        }
+        match reminder.target {
+            Some(Target::Channel(_)) => info.push("sent to another channel".to_string()),
+            Some(Target::Dm(_)) => info.push("sent by DM".to_string()),
+            None => (),
         }
+        if reminder.webhook.is_some() {
+            info.push("posted to a webhook".to_string());
+        }
+        if let Some(end) = reminder.ends_at {
+            info.push(format!(
+                "until {}",
+                end.with_timezone(&self.tz).format("%Y-%m-%d")
+            ));
+        }
+        let datetime = next.map_or(String::new(), |t| t.with_timezone(&self.tz).to_rfc2822());
+
+        limit_length(&format!("{datetime} ({})", info.join(", ")), OPTION_LENGTH)
+    }
+
+    // Shows how the selected reminder looks if it is sent as an embed
+    fn preview(&self, selected: Option<&Entry<'_>>) -> Option<CreateEmbed> {
+        let (key, reminder, next) = selected?;
+        let footer = next.map_or(String::new(), |t| {
+            format!(
+                "Next at {}",
+                t.with_timezone(&self.tz).format("%Y-%m-%d %H:%M %Z")
+            )
+        });
+
+        let mut embed = CreateEmbed::default();
+        reminder.embed.as_ref()?.render(
+            &mut embed,
+            &self.rendered(*key, reminder, *next),
+            &footer,
+            next.unwrap_or_else(Utc::now),
+        );
+        Some(embed)
+    }
+
+    // Timezone of the menu and details of the selected reminder
+    fn content(&self, selected: Option<&Entry<'_>>) -> String {
+        let mut content = vec![format!("Timezone: {}", self.tz)];
+        if let Some((key, reminder, next)) = selected {
+            if let Some(target) = reminder.target {
+                content.push(format!("Sent to: {target}"));
+            }
+            // Webhook URLs often carry tokens, and everyone can see the menu
+            if let Some(webhook) = &reminder.webhook {
+                let url = reqwest::Url::parse(&webhook.url).ok();
+                let host = url.as_ref().and_then(reqwest::Url::host_str).unwrap_or("?");
+                content.push(format!("Webhook: {host}"));
+            }
+            if !reminder.mentions.is_empty() {
+                content.push(format!("Pings: {}", reminder.mentions));
+            }
+            let rendered = self.rendered(*key, reminder, *next);
+            if rendered != reminder.msg {
+                content.push(format!("Template: {}", reminder.msg));
+                content.push(format!("Next message: {rendered}"));
+            }
+        }
+
+        content.join("\n")
     }
 
-    pub fn create<'a>(
+    pub fn create<'a, 'b>(
         &self,
-        message: &'a mut CreateInteractionResponseData,
-    ) -> &'a mut CreateInteractionResponseData {
-        if self.reminders.len() > 0 {
-            message
-                .content(format!("Channel timezone: {}", self.tz))
-                .components(|comps| {
-                    comps
-                        .create_action_row(|ar| {
-                            ar.create_select_menu(|sm| {
-                                sm.min_values(0)
-                                    .custom_id("menu-reminders")
-                                    .options(|opts| {
-                                        self.reminders.iter().fold(opts, |opts, (k, r)| {
+        message: &'b mut CreateInteractionResponseData<'a>,
+    ) -> &'b mut CreateInteractionResponseData<'a> {
+        if self.reminders.is_empty() {
+            return message
+                .content("no reminders")
+                .set_embeds(Vec::new())
+                .components(|comps| comps);
+        }
+
+        let page = self.page();
+        let pages = self.pages();
+        let sorted = self.sorted();
+
+        let selected = self.selected.as_ref().and_then(|sel| {
+            let key = serde_json::from_str(sel).ok()?;
+            sorted.iter().find(|(k, ..)| *k == key)
+        });
+
+        message
+            .content(self.content(selected))
+            .set_embeds(self.preview(selected))
+            // Showing who gets pinged should not ping them
+            .allowed_mentions(|am| am.empty_parse())
+            .components(|comps| {
+                comps
+                    .create_action_row(|ar| {
+                        ar.create_select_menu(|sm| {
+                            sm.min_values(0)
+                                .custom_id(format!("menu-{page}-reminders"))
+                                .options(|opts| {
+                                    sorted.iter().skip(page * PAGE_SIZE).take(PAGE_SIZE).fold(
+                                        opts,
+                                        |opts, (k, r, next)| {
                                             opts.create_option(|opt| {
                                                 // This should never panic, any key should be
                                                 // stringifiable
                                                 let key = serde_json::to_string(k)
                                                     .expect("Error serializing key");
-                                                let (info, datetime) = match &r.reminder_type {
-                                                    ReminderType::Scheduled(sched) => (
-                                                        "Repeating",
-                                                        sched.upcoming(self.tz).next(),
-                                                    ),
-                                                    ReminderType::Once(datetime) => (
-                                                        "One-shot",
-                                                        Some(self.tz.from_utc_datetime(&datetime)),
-                                                    ),
-                                                };
-                                                let datetime = datetime
-                                                    .map(|t| t.to_rfc2822())
-                                                    .unwrap_or("".to_string());
-
-                                                opt.label(limit_length(&r.msg, 100))
-                                                    .description(format!("{} ({})", datetime, info))
+                                                opt.label(limit_length(&r.msg, OPTION_LENGTH))
+                                                    .description(self.describe(r, *next))
                                                     .value(key.clone())
                                                     .default_selection(
                                                         self.selected
                                                             .as_ref()
-                                                            .map(|s| *s == key)
-                                                            .unwrap_or(false),
+                                                            .is_some_and(|s| *s == key),
                                                     )
                                             })
-                                        })
-                                    })
-                            })
+                                        },
+                                    )
+                                })
                         })
-                        .create_action_row(|ar| {
+                    })
+                    .create_action_row(|ar| {
+                        let selected = self.selected.as_ref().and_then(|sel| {
+                            let key = serde_json::from_str(sel).ok()?;
+                            Some((sel, self.reminders.get(&key)?))
+                        });
+                        // Pausing and skipping only make sense for repeating reminders
+                        let recurring =
+                            selected.is_some_and(|(_, r)| r.reminder_type.is_repeating());
+                        let pause = if selected.is_some_and(|(_, r)| r.paused) {
+                            ("resume", "Resume")
+                        } else {
+                            ("pause", "Pause")
+                        };
+
+                        [
+                            ("edit", "Edit", ButtonStyle::Primary, true),
+                            (pause.0, pause.1, ButtonStyle::Secondary, recurring),
+                            ("skip", "Skip next", ButtonStyle::Secondary, recurring),
+                            ("delete", "Delete", ButtonStyle::Danger, true),
+                        ]
+                        .into_iter()
+                        .fold(ar, |ar, (action, label, style, enabled)| {
                             ar.create_button(|b| {
-                                let b = b.style(ButtonStyle::Danger).label("Delete");
-                                if let Some(sel) = &self.selected {
-                                    b.custom_id(format!("menu-{}", &sel))
-                                } else {
-                                    b.custom_id("menu-delete").disabled(true)
+                                let b = b.style(style).label(label);
+                                match selected {
+                                    Some((sel, _)) if enabled => {
+                                        b.custom_id(format!("menu-{page}-{action}-{sel}"))
+                                    }
+                                    _ => {
+                                        b.custom_id(format!("menu-{page}-{action}")).disabled(true)
+                                    }
                                 }
                             })
                         })
-                })
-        } else {
-            message.content("no reminders").components(|comps| comps)
-        }
+                    });
+
+                if pages > 1 {
+                    comps.create_action_row(|ar| {
+                        Self::create_pages(ar, page, pages, self.selected.as_deref())
+                    });
+                }
+
+                comps
+            })
+    }
+
+    // Previous/Next buttons around a page indicator, they carry the selected
+    // reminder over to the other page
+    fn create_pages<'a>(
+        ar: &'a mut CreateActionRow,
+        page: usize,
+        pages: usize,
+        selected: Option<&str>,
+    ) -> &'a mut CreateActionRow {
+        let custom_id = |direction: &str| match selected {
+            Some(sel) => format!("menu-{page}-{direction}-{sel}"),
+            None => format!("menu-{page}-{direction}"),
+        };
+
+        ar.create_button(|b| {
+            b.style(ButtonStyle::Secondary)
+                .label("Previous")
+                .custom_id(custom_id("prev"))
+                .disabled(page == 0)
+        })
+        .create_button(|b| {
+            b.style(ButtonStyle::Secondary)
+                .label(format!("Page {}/{pages}", page + 1))
+                .custom_id(format!("menu-{page}-page"))
+                .disabled(true)
+        })
+        .create_button(|b| {
+            b.style(ButtonStyle::Secondary)
+                .label("Next")
+                .custom_id(custom_id("next"))
+                .disabled(page + 1 == pages)
+        })
     }
 
     pub async fn handle(
@@ -118,19 +397,71 @@ impl ReminderMenu {
         manager: &Manager,
         message: &MessageComponentInteraction,
     ) {
-        match message.data.component_type {
-            ComponentType::SelectMenu => {
-                self.selected = message.data.values.get(0).cloned();
+        // This should never panic because the custom_id always starts with the page
+        // we generated in ReminderMenu::create
+        let (page, id) = message
+            .data
+            .custom_id
+            .strip_prefix("menu-")
+            .and_then(|id| id.split_once('-'))
+            .unwrap();
+        self.page = page.parse().expect("Error parsing page");
+
+        match (message.data.component_type, id.split_once('-')) {
+            (ComponentType::SelectMenu, _) => {
+                self.selected = message.data.values.first().cloned();
+            }
+            (ComponentType::Button, None) => self.turn_page(id),
+            (ComponentType::Button, Some((direction @ ("prev" | "next"), key_str))) => {
+                self.selected = Some(key_str.to_string());
+                self.turn_page(direction);
             }
-            ComponentType::Button => {
-                // This should never panic because the custom_id is always the valid json string
-                // we generated in ReminderMenu::create
-                let key =
-                    serde_json::from_str(&message.data.custom_id.strip_prefix("menu-").unwrap())
-                        .expect("Error deserializing key");
-                self.reminders.remove(&key);
-                self.selected = None;
-                manager.remove_reminder(message.channel_id, key).await;
+            // The rest of the custom_id is always the action and a valid json string
+            (ComponentType::Button, Some((action, key_str))) => {
+                let key = serde_json::from_str(key_str).expect("Error deserializing key");
+                let user = message.user.id;
+                let member = message.member.as_ref();
+                if let Err(why) = manager
+                    .authorize(message.channel_id, key, user, member)
+                    .await
+                {
+                    Self::refuse(&ctx, message, why).await;
+                    return;
+                }
+
+                if action == "edit" {
+                    // The modal replaces the usual message update, the menu is updated once
+                    // it is submitted
+                    if let Some(reminder) = self.reminders.get(&key) {
+                        self.edit(&ctx, message, key_str, reminder).await;
+                    }
+                    return;
+                }
+
+                let result = match action {
+                    "pause" => manager.pause_reminder(message.channel_id, key).await,
+                    "resume" => manager.resume_reminder(message.channel_id, key).await,
+                    "skip" => manager.skip_reminder(message.channel_id, key).await,
+                    "delete" => {
+                        self.reminders.remove(&key);
+                        self.selected = None;
+                        manager.remove_reminder(message.channel_id, key).await;
+                        Ok(())
+                    }
+                    _ => Ok(()),
+                };
+
+                if let Err(why) = result {
+                    println!("Cannot {action} reminder: {why}");
+                } else if let Some(reminder) = manager
+                    .channel_data(message.channel_id)
+                    .await
+                    .and_then(|cd| cd.reminders.get(key).cloned())
+                {
+                    // Pausing or skipping changes the next fire time and with it the order
+                    self.reminders.insert(key, reminder);
+                    self.select(key_str);
+                }
             }
             _ => (),
         }
@@ -143,7 +474,301 @@ impl ReminderMenu {
             })
             .await
         {
-            println!("Cannot respond to component interaction: {:#?}", why);
+            println!("Cannot respond to component interaction: {why:#?}");
         }
     }
+
+    // Tells the user an action is not allowed, without touching the menu
+    async fn refuse(ctx: &Context, message: &MessageComponentInteraction, why: &str) {
+        if let Err(why) = message
+            .create_interaction_response(&ctx.http, |response| {
+                response.interaction_response_data(|m| m.content(why).ephemeral(true))
+            })
+            .await
+        {
+            println!("Cannot respond to component interaction: {why:#?}");
+        }
+    }
+
+    // Opens a modal pre-filled with the reminder's message and cron fields, or its
+    // recurrence rule
+    async fn edit(
+        &self,
+        ctx: &Context,
+        message: &MessageComponentInteraction,
+        key: &str,
+        reminder: &Reminder,
+    ) {
+        let (label, style, schedule) =
+            if let ReminderType::Recurring(rrule) = &reminder.reminder_type {
+                (
+                    "Recurrence rule".to_string(),
+                    InputTextStyle::Paragraph,
+                    rrule.to_string(),
+                )
+            } else {
+                // Every other reminder type can be described by cron fields
+                let fields = reminder
+                    .reminder_type
+                    .cron_fields(self.tz)
+                    .unwrap_or_default();
+                (
+                    format!("Schedule ({})", CRON_FIELDS.join(" ")),
+                    InputTextStyle::Short,
+                    fields.join(" "),
+                )
+            };
+
+        if let Err(why) = message
+            .create_interaction_response(&ctx.http, |response| {
+                response
+                    .kind(InteractionResponseType::Modal)
+                    .interaction_response_data(|modal| {
+                        modal
+                            .custom_id(format!("edit-{key}"))
+                            .title("Edit reminder")
+                            .components(|comps| {
+                                comps
+                                    .create_action_row(|ar| {
+                                        ar.create_input_text(|it| {
+                                            it.custom_id("msg")
+                                                .label("Message")
+                                                .style(InputTextStyle::Paragraph)
+                                                .value(&reminder.msg)
+                                                .required(true)
+                                        })
+                                    })
+                                    .create_action_row(|ar| {
+                                        ar.create_input_text(|it| {
+                                            it.custom_id("schedule")
+                                                .label(label)
+                                                .style(style)
+                                                .value(schedule)
+                                                .required(true)
+                                        })
+                                    })
+                            })
+                    })
+            })
+            .await
+        {
+            println!("Cannot respond to component interaction: {why:#?}");
+        }
+    }
+
+    /// Applies the changes submitted through the edit modal
+    pub async fn handle_edit(
+        ctx: Arc<Context>,
+        manager: &Manager,
+        submit: &ModalSubmitInteraction,
+    ) {
+        // This should never panic because the custom_id is always the valid json string
+        // we generated in ReminderMenu::edit
+        let key_str = submit.data.custom_id.strip_prefix("edit-").unwrap();
+        let key = serde_json::from_str(key_str).expect("Error deserializing key");
+        let inputs = submit
+            .data
+            .components
+            .iter()
+            .flat_map(|ar| &ar.components)
+            .filter_map(|c| match c {
+                ActionRowComponent::InputText(it) => Some((it.custom_id.as_str(), it.value.trim())),
+                _ => None,
+            })
+            .collect::<HashMap<_, _>>();
+
+        let mut menu = ReminderMenu::new(manager, submit.channel_id, submit.user.id).await;
+        // Permissions may have changed since the modal was opened
+        let allowed = manager
+            .authorize(
+                submit.channel_id,
+                key,
+                submit.user.id,
+                submit.member.as_ref(),
+            )
+            .await;
+        let edited = allowed
+            .map_err(ToString::to_string)
+            .and_then(|()| menu.edited(&inputs, key));
+        let error = match edited {
+            Ok(reminder) => {
+                manager
+                    .edit_reminder(submit.channel_id, key, reminder.clone())
+                    .await;
+                menu.reminders.insert(key, reminder);
+                menu.select(key_str);
+                None
+            }
+            Err(why) => Some(why),
+        };
+
+        if let Err(why) = submit
+            .create_interaction_response(&ctx.http, move |response| {
+                if let Some(why) = error {
+                    response
+                        .interaction_response_data(|message| message.content(why).ephemeral(true))
+                } else {
+                    response
+                        .kind(InteractionResponseType::UpdateMessage)
+                        .interaction_response_data(|message| menu.create(message))
+                }
+            })
+            .await
+        {
+            println!("Cannot respond to modal submission: {why:#?}");
+        }
+    }
+
+    // Validates the modal inputs and builds the updated reminder
+    fn edited(&self, inputs: &HashMap<&str, &str>, key: DefaultKey) -> Result<Reminder, String> {
+        let mut reminder = self
+            .reminders
+            .get(&key)
+            .ok_or("reminder no longer exists")?
+            .clone();
+
+        let msg = inputs.get("msg").copied().unwrap_or_default();
+        if msg.is_empty() {
+            return Err("message cannot be empty".to_string());
+        }
+        template::validate(msg)?;
+
+        let schedule = inputs.get("schedule").copied().unwrap_or_default();
+        reminder.reminder_type = match reminder.reminder_type {
+            ReminderType::Once(_) => ReminderType::Once(
+                parse_cron_fields(schedule)?
+                    .upcoming(self.tz)
+                    .next()
+                    .ok_or("schedule has no upcoming times")?
+                    .naive_utc(),
+            ),
+            ReminderType::Scheduled(_) => ReminderType::Scheduled(parse_cron_fields(schedule)?),
+            ReminderType::Recurring(_) => {
+                ReminderType::Recurring(schedule.parse().map_err(|_| "invalid recurrence rule")?)
+            }
+        };
+        reminder.msg = msg.to_string();
+
+        Ok(reminder)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::reminder::{Nag, Webhook};
+    use chrono_tz::Europe::Madrid;
+    use std::str::FromStr;
+
+    fn menu(reminders: Vec<Reminder>) -> (ReminderMenu, Vec<DefaultKey>) {
+        let mut keys = slotmap::SlotMap::new();
+        let reminders = reminders
+            .into_iter()
+            .map(|r| (keys.insert(()), r))
+            .collect::<Vec<_>>();
+        let order = reminders.iter().map(|(k, _)| *k).collect();
+
+        let menu = ReminderMenu {
+            tz: Madrid,
+            reminders: reminders.into_iter().collect(),
+            zones: HashMap::new(),
+            selected: None,
+            page: 0,
+        };
+        (menu, order)
+    }
+
+    fn daily(msg: &str) -> Reminder {
+        Reminder::new(
+            ReminderType::Scheduled(Schedule::from_str("0 0 9 * * * *").unwrap()),
+            msg.to_string(),
+        )
+    }
+
+    fn once(datetime: &str, msg: &str) -> Reminder {
+        Reminder::new(
+            ReminderType::Once(
+                chrono::NaiveDateTime::parse_from_str(datetime, "%Y-%m-%d %H:%M").unwrap(),
+            ),
+            msg.to_string(),
+        )
+    }
+
+    #[test]
+    fn soonest_reminders_come_first() {
+        let (menu, keys) = menu(vec![
+            once("2000-01-01 09:00", "past"),
+            Reminder {
+                paused: true,
+                ..daily("paused")
+            },
+            once("2099-01-01 09:00", "later"),
+            daily("daily"),
+        ]);
+
+        let order = menu.sorted().iter().map(|(k, ..)| *k).collect::<Vec<_>>();
+        // Reminders that will not be sent go last, in the order they were created
+        assert_eq!(order, [keys[3], keys[2], keys[0], keys[1]]);
+    }
+
+    #[test]
+    fn pages_stay_in_bounds() {
+        let (mut menu, keys) = menu((0..30).map(|i| daily(&i.to_string())).collect());
+        assert_eq!(menu.pages(), 2);
+
+        menu.turn_page("prev");
+        assert_eq!(menu.page(), 0);
+        menu.turn_page("next");
+        menu.turn_page("next");
+        assert_eq!(menu.page(), 1);
+        menu.turn_page("prev");
+        assert_eq!(menu.page(), 0);
+
+        // Removing reminders can leave the stored page past the end
+        menu.page = 1;
+        for key in &keys[..10] {
+            menu.reminders.remove(key);
+        }
+        assert_eq!(menu.pages(), 1);
+        assert_eq!(menu.page(), 0);
+        menu.turn_page("next");
+        assert_eq!(menu.page, 0);
+
+        menu.reminders.clear();
+        assert_eq!(menu.pages(), 1);
+        assert_eq!(menu.page(), 0);
+    }
+
+    #[test]
+    fn selecting_moves_to_its_page() {
+        let (mut menu, _) = menu((0..30).map(|i| daily(&i.to_string())).collect());
+        let last = menu.sorted()[29].0;
+
+        menu.select(&serde_json::to_string(&last).unwrap());
+        assert_eq!(menu.page(), 1);
+    }
+
+    #[test]
+    fn descriptions_fit_in_options() {
+        let reminder = Reminder {
+            count: Some(1000),
+            nag: Some(Nag::new(1440, Some(10))),
+            target: Some(Target::Channel(ChannelId(1))),
+            webhook: Some(Webhook {
+                url: "https://example.com/hook".to_string(),
+                secret: "secret".to_string(),
+                discord: true,
+            }),
+            ends_at: Some(Utc::now() + chrono::Duration::days(365)),
+            ..Reminder::new(
+                ReminderType::Scheduled(Schedule::from_str("0 0 9 * * * *").unwrap()),
+                "standup".to_string(),
+            )
+        };
+        let (menu, _) = menu(Vec::new());
+
+        let description = menu.describe(&reminder, Some(Utc::now()));
+        assert!(description.chars().count() <= OPTION_LENGTH);
+        assert!(description.ends_with("..."));
+    }
 }
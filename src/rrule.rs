@@ -0,0 +1,696 @@
+use crate::time_parser::to_utc;
+use chrono::{
+    DateTime, Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc, Weekday,
+};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use std::{fmt, str::FromStr};
+
+// Years in a row without any occurrence before a rule is considered exhausted,
+// enough for rules that only match on leap days, which can be 8 years apart
+const MAX_EMPTY_YEARS: u32 = 8;
+
+const WEEKDAYS: [(&str, Weekday); 7] = [
+    ("MO", Weekday::Mon),
+    ("TU", Weekday::Tue),
+    ("WE", Weekday::Wed),
+    ("TH", Weekday::Thu),
+    ("FR", Weekday::Fri),
+    ("SA", Weekday::Sat),
+    ("SU", Weekday::Sun),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Freq {
+    Daily,
+    Weekly,
+    Monthly,
+    Yearly,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Until {
+    Local(NaiveDateTime),
+    Utc(DateTime<Utc>),
+}
+
+/// Recurrence rule following RFC 5545, with its start and excluded dates
+///
+/// Supports FREQ (DAILY to YEARLY), INTERVAL, BYMONTH, BYMONTHDAY, BYDAY with
+/// ordinals, BYSETPOS, COUNT and UNTIL. Times are local to the channel, so the
+/// rule is written and stored without a timezone
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct RRule {
+    source: String,
+    start: NaiveDateTime,
+    freq: Freq,
+    interval: u32,
+    by_month: Vec<u32>,
+    by_month_day: Vec<i32>,
+    by_day: Vec<(Option<i32>, Weekday)>,
+    by_set_pos: Vec<i32>,
+    count: Option<u32>,
+    until: Option<Until>,
+    // Dates without a time exclude every occurrence on that day
+    exdates: Vec<NaiveDateTime>,
+    exdays: Vec<NaiveDate>,
+}
+
+fn parse_date(value: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(value, "%Y%m%d").map_err(|_| format!("invalid date {value}"))
+}
+
+fn parse_datetime(value: &str) -> Result<NaiveDateTime, String> {
+    NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S")
+        .map_err(|_| format!("invalid date-time {value}"))
+}
+
+fn parse_list<T: FromStr>(value: &str, valid: impl Fn(&T) -> bool) -> Result<Vec<T>, String> {
+    value
+        .split(',')
+        .map(|v| {
+            v.parse()
+                .ok()
+                .filter(&valid)
+                .ok_or(format!("invalid value {v}"))
+        })
+        .collect()
+}
+
+fn parse_weekday(value: &str) -> Result<(Option<i32>, Weekday), String> {
+    let split = value.len().saturating_sub(2);
+    let (ordinal, day) = value.split_at(split);
+    let weekday = WEEKDAYS
+        .iter()
+        .find(|(name, _)| *name == day)
+        .map(|(_, weekday)| *weekday)
+        .ok_or(format!("invalid weekday {value}"))?;
+    let ordinal = match ordinal {
+        "" => None,
+        _ => Some(
+            ordinal
+                .parse()
+                .ok()
+                .filter(|n: &i32| *n != 0 && n.abs() <= 53)
+                .ok_or(format!("invalid weekday {value}"))?,
+        ),
+    };
+
+    Ok((ordinal, weekday))
+}
+
+fn parse_freq(value: &str) -> Result<Freq, String> {
+    match value {
+        "DAILY" => Ok(Freq::Daily),
+        "WEEKLY" => Ok(Freq::Weekly),
+        "MONTHLY" => Ok(Freq::Monthly),
+        "YEARLY" => Ok(Freq::Yearly),
+        _ => Err(format!("FREQ={value} is not supported")),
+    }
+}
+
+impl FromStr for RRule {
+    type Err = String;
+
+    /// Parses DTSTART, RRULE and EXDATE lines, which may also be separated by
+    /// spaces. The RRULE prefix can be left out
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.to_uppercase();
+        let mut lines = Vec::new();
+        let mut start = None;
+        let mut parts = None;
+        let mut exdates = Vec::new();
+
+        for line in s.split_whitespace() {
+            match line.split_once(':') {
+                Some(("DTSTART", value)) => start = Some(parse_datetime(value)?),
+                Some(("RRULE", value)) => parts = Some(value),
+                Some(("EXDATE", value)) => exdates.extend(value.split(',')),
+                Some((name, _)) if name.contains(';') => {
+                    return Err(format!(
+                        "{name} is not supported, times are in the channel's timezone"
+                    ))
+                }
+                Some((name, _)) => return Err(format!("unknown property {name}")),
+                None => {
+                    parts = Some(line);
+                    lines.push(format!("RRULE:{line}"));
+                    continue;
+                }
+            }
+            lines.push(line.to_string());
+        }
+
+        let mut rrule = Self {
+            source: lines.join("\n"),
+            start: start.ok_or("missing DTSTART")?,
+            freq: Freq::Daily,
+            interval: 1,
+            by_month: Vec::new(),
+            by_month_day: Vec::new(),
+            by_day: Vec::new(),
+            by_set_pos: Vec::new(),
+            count: None,
+            until: None,
+            exdates: Vec::new(),
+            exdays: Vec::new(),
+        };
+
+        let mut freq = None;
+        for part in parts.ok_or("missing RRULE")?.split(';') {
+            let (name, value) = part
+                .split_once('=')
+                .ok_or(format!("invalid rule part {part}"))?;
+            if name == "FREQ" {
+                freq = Some(parse_freq(value)?);
+            } else {
+                rrule.set_part(name, value)?;
+            }
+        }
+        rrule.freq = freq.ok_or("missing FREQ")?;
+
+        for exdate in exdates {
+            if exdate.contains('T') {
+                rrule.exdates.push(parse_datetime(exdate)?);
+            } else {
+                rrule.exdays.push(parse_date(exdate)?);
+            }
+        }
+
+        rrule.validate()?;
+        Ok(rrule)
+    }
+}
+
+impl TryFrom<String> for RRule {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<RRule> for String {
+    fn from(rrule: RRule) -> Self {
+        rrule.source
+    }
+}
+
+impl fmt::Display for RRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+fn days_in_month(year: i32, month: u32) -> u32 {
+    (28..=31)
+        .rev()
+        .find(|d| NaiveDate::from_ymd_opt(year, month, *d).is_some())
+        .unwrap_or(28)
+}
+
+// Whether the date is the n-th (or n-th to last) of its weekday between first
+// and last
+fn nth_in(date: NaiveDate, ordinal: i32, first: NaiveDate, last: NaiveDate) -> bool {
+    let nth = |from: NaiveDate, to: NaiveDate| (to - from).num_days().abs() / 7 + 1;
+    if ordinal > 0 {
+        nth(first, date) == i64::from(ordinal)
+    } else {
+        nth(date, last) == i64::from(-ordinal)
+    }
+}
+
+impl RRule {
+    fn set_part(&mut self, name: &str, value: &str) -> Result<(), String> {
+        match name {
+            "INTERVAL" => {
+                self.interval = value
+                    .parse()
+                    .ok()
+                    .filter(|i| *i > 0)
+                    .ok_or("invalid INTERVAL")?;
+            }
+            "BYMONTH" => self.by_month = parse_list(value, |m| (1..=12).contains(m))?,
+            "BYMONTHDAY" => {
+                self.by_month_day = parse_list(value, |d: &i32| *d != 0 && d.abs() <= 31)?;
+            }
+            "BYDAY" => {
+                self.by_day = value
+                    .split(',')
+                    .map(parse_weekday)
+                    .collect::<Result<_, _>>()?;
+            }
+            "BYSETPOS" => {
+                self.by_set_pos = parse_list(value, |p: &i32| *p != 0 && p.abs() <= 366)?;
+            }
+            "COUNT" => {
+                self.count = Some(
+                    value
+                        .parse()
+                        .ok()
+                        .filter(|c| *c > 0)
+                        .ok_or("invalid COUNT")?,
+                );
+            }
+            "UNTIL" => {
+                self.until = Some(if let Some(utc) = value.strip_suffix('Z') {
+                    Until::Utc(Utc.from_utc_datetime(&parse_datetime(utc)?))
+                } else if value.contains('T') {
+                    Until::Local(parse_datetime(value)?)
+                } else {
+                    // A date on its own includes the whole day
+                    Until::Local(
+                        parse_date(value)?
+                            .and_hms_opt(23, 59, 59)
+                            .ok_or("invalid UNTIL")?,
+                    )
+                });
+            }
+            _ => return Err(format!("{name} is not supported")),
+        }
+
+        Ok(())
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        if self.count.is_some() && self.until.is_some() {
+            return Err("COUNT and UNTIL cannot be used together".to_string());
+        }
+        let ordinals = self.by_day.iter().any(|(ordinal, _)| ordinal.is_some());
+        if ordinals && !matches!(self.freq, Freq::Monthly | Freq::Yearly) {
+            return Err("BYDAY ordinals need FREQ=MONTHLY or FREQ=YEARLY".to_string());
+        }
+        if self.freq == Freq::Weekly && !self.by_month_day.is_empty() {
+            return Err("BYMONTHDAY cannot be used with FREQ=WEEKLY".to_string());
+        }
+
+        Ok(())
+    }
+
+    fn matches_day(&self, date: NaiveDate, first: NaiveDate, last: NaiveDate) -> bool {
+        self.by_day.is_empty()
+            || self.by_day.iter().any(|(ordinal, weekday)| {
+                date.weekday() == *weekday && ordinal.is_none_or(|n| nth_in(date, n, first, last))
+            })
+    }
+
+    fn month_days(&self, year: i32, month: u32) -> Vec<NaiveDate> {
+        let len = days_in_month(year, month);
+        // Months out of the range of dates we can represent have no days
+        let (Some(first), Some(last)) = (
+            NaiveDate::from_ymd_opt(year, month, 1),
+            NaiveDate::from_ymd_opt(year, month, len),
+        ) else {
+            return Vec::new();
+        };
+
+        let days = if !self.by_month_day.is_empty() {
+            self.by_month_day
+                .iter()
+                .filter_map(|d| {
+                    let d = if *d > 0 {
+                        d.unsigned_abs()
+                    } else {
+                        (len + 1).checked_sub(d.unsigned_abs())?
+                    };
+                    NaiveDate::from_ymd_opt(year, month, d)
+                })
+                .collect()
+        } else if !self.by_day.is_empty() {
+            first.iter_days().take(len as usize).collect()
+        } else {
+            NaiveDate::from_ymd_opt(year, month, self.start.day())
+                .into_iter()
+                .collect::<Vec<_>>()
+        };
+
+        days.into_iter()
+            .filter(|d| self.matches_day(*d, first, last))
+            .collect()
+    }
+
+    // Candidate days of the n-th period after the start, before BYSETPOS, or None
+    // once the period is out of the range of dates we can represent
+    fn period(&self, n: u32) -> Option<Vec<NaiveDate>> {
+        let step = i64::from(n) * i64::from(self.interval);
+        let start = self.start.date();
+        let in_month =
+            |d: &NaiveDate| self.by_month.is_empty() || self.by_month.contains(&d.month());
+
+        let days = match self.freq {
+            Freq::Daily => {
+                let day = start.checked_add_signed(Duration::days(step))?;
+                let month_day = self.by_month_day.is_empty()
+                    || self.month_days(day.year(), day.month()).contains(&day);
+                if month_day && self.matches_day(day, day, day) {
+                    vec![day]
+                } else {
+                    vec![]
+                }
+            }
+            Freq::Weekly => {
+                let monday =
+                    start - Duration::days(i64::from(start.weekday().num_days_from_monday()));
+                let monday = monday.checked_add_signed(Duration::weeks(step))?;
+                monday
+                    .iter_days()
+                    .take(7)
+                    .filter(|d| {
+                        if self.by_day.is_empty() {
+                            d.weekday() == start.weekday()
+                        } else {
+                            self.matches_day(*d, *d, *d)
+                        }
+                    })
+                    .collect()
+            }
+            Freq::Monthly => {
+                let months = i64::from(start.month0()) + step;
+                let year = i32::try_from(i64::from(start.year()) + months / 12).ok()?;
+                NaiveDate::from_ymd_opt(year, 1, 1)?;
+                self.month_days(year, u32::try_from(months % 12).ok()? + 1)
+            }
+            Freq::Yearly => {
+                let year = i32::try_from(i64::from(start.year()) + step).ok()?;
+                let first = NaiveDate::from_ymd_opt(year, 1, 1)?;
+                let last = NaiveDate::from_ymd_opt(year, 12, 31)?;
+                if !self.by_month.is_empty() {
+                    self.by_month
+                        .iter()
+                        .flat_map(|m| self.month_days(year, *m))
+                        .collect()
+                } else if !self.by_month_day.is_empty() {
+                    (1..=12).flat_map(|m| self.month_days(year, m)).collect()
+                } else if !self.by_day.is_empty() {
+                    // Ordinals count weekdays within the year
+                    first
+                        .iter_days()
+                        .take_while(|d| *d <= last)
+                        .filter(|d| self.matches_day(*d, first, last))
+                        .collect()
+                } else {
+                    self.month_days(year, start.month())
+                }
+            }
+        };
+
+        let mut days = days.into_iter().filter(in_month).collect::<Vec<_>>();
+        days.sort_unstable();
+        days.dedup();
+
+        if self.by_set_pos.is_empty() {
+            return Some(days);
+        }
+        let mut picked = self
+            .by_set_pos
+            .iter()
+            .filter_map(|p| {
+                let pos = usize::try_from(p.unsigned_abs()).ok()?;
+                let i = if *p > 0 {
+                    pos - 1
+                } else {
+                    days.len().checked_sub(pos)?
+                };
+                days.get(i).copied()
+            })
+            .collect::<Vec<_>>();
+        picked.sort_unstable();
+        picked.dedup();
+
+        Some(picked)
+    }
+
+    // Periods in `MAX_EMPTY_YEARS`
+    fn max_empty_periods(&self) -> u32 {
+        let per_year = match self.freq {
+            Freq::Daily => 366,
+            Freq::Weekly => 53,
+            Freq::Monthly => 12,
+            Freq::Yearly => 1,
+        };
+        (MAX_EMPTY_YEARS * per_year).div_ceil(self.interval).max(1)
+    }
+
+    // Number of a period starting before the given day, so that none of the
+    // occurrences from that day on are in earlier periods
+    fn period_before(&self, date: NaiveDate) -> u32 {
+        let start = self.start.date();
+        let elapsed = match self.freq {
+            Freq::Daily => (date - start).num_days(),
+            Freq::Weekly => {
+                let monday =
+                    start - Duration::days(i64::from(start.weekday().num_days_from_monday()));
+                (date - monday).num_days() / 7
+            }
+            Freq::Monthly => {
+                i64::from(date.year() - start.year()) * 12 + i64::from(date.month0())
+                    - i64::from(start.month0())
+            }
+            Freq::Yearly => i64::from(date.year() - start.year()),
+        };
+
+        // Timezones can move the day by one either way
+        u32::try_from(elapsed / i64::from(self.interval) - 1).unwrap_or(0)
+    }
+
+    /// Local times of every occurrence from the given period on, including the
+    /// ones excluded by EXDATE
+    fn local_occurrences(&self, first: u32) -> impl Iterator<Item = NaiveDateTime> + '_ {
+        let time: NaiveTime = self.start.time();
+        let max_empty = self.max_empty_periods();
+        let mut empty = 0;
+
+        (first..)
+            .map_while(move |n| {
+                let days = self.period(n)?;
+                empty = if days.is_empty() { empty + 1 } else { 0 };
+                (empty < max_empty).then_some(days)
+            })
+            .flatten()
+            .map(move |d| d.and_time(time))
+            .filter(|t| *t >= self.start)
+            .take(self.count.map_or(usize::MAX, |c| c as usize))
+    }
+
+    /// Occurrences in order, resolved in the given timezone
+    pub fn occurrences(&self, tz: Tz) -> impl Iterator<Item = DateTime<Utc>> + '_ {
+        self.occurrences_from(tz, 0)
+    }
+
+    fn occurrences_from(&self, tz: Tz, first: u32) -> impl Iterator<Item = DateTime<Utc>> + '_ {
+        let until = self.until.and_then(|until| match until {
+            Until::Local(local) => to_utc(tz, local),
+            Until::Utc(utc) => Some(utc),
+        });
+
+        self.local_occurrences(first)
+            .filter(|t| !self.exdates.contains(t) && !self.exdays.contains(&t.date()))
+            .filter_map(move |t| to_utc(tz, t))
+            .take_while(move |t| until.is_none_or(|until| *t <= until))
+    }
+
+    /// First occurrence strictly after the given time
+    pub fn next_after(&self, tz: Tz, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        // COUNT is counted from the start, otherwise the periods before the given
+        // time cannot hold the next occurrence
+        let first = match self.count {
+            Some(_) => 0,
+            None => self.period_before(after.with_timezone(&tz).naive_local().date()),
+        };
+
+        self.occurrences_from(tz, first).find(|t| *t > after)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono_tz::Europe::Madrid;
+
+    fn dates(rule: &str, n: usize) -> Vec<String> {
+        rule.parse::<RRule>()
+            .unwrap()
+            .occurrences(Madrid)
+            .take(n)
+            .map(|t| {
+                t.with_timezone(&Madrid)
+                    .format("%Y-%m-%d %H:%M")
+                    .to_string()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn every_other_tuesday() {
+        assert_eq!(
+            dates(
+                "DTSTART:20261201T090000 RRULE:FREQ=WEEKLY;INTERVAL=2;BYDAY=TU",
+                3
+            ),
+            ["2026-12-01 09:00", "2026-12-15 09:00", "2026-12-29 09:00"]
+        );
+    }
+
+    #[test]
+    fn last_weekday_of_month() {
+        assert_eq!(
+            dates(
+                "DTSTART:20260101T170000\nRRULE:FREQ=MONTHLY;BYDAY=MO,TU,WE,TH,FR;BYSETPOS=-1",
+                4
+            ),
+            [
+                "2026-01-30 17:00",
+                "2026-02-27 17:00",
+                "2026-03-31 17:00",
+                "2026-04-30 17:00"
+            ]
+        );
+    }
+
+    #[test]
+    fn every_three_days_with_exdate() {
+        assert_eq!(
+            dates(
+                "DTSTART:20260328T080000 FREQ=DAILY;INTERVAL=3;COUNT=4 EXDATE:20260403T080000",
+                10
+            ),
+            // The DST change on 03-29 keeps the local time
+            ["2026-03-28 08:00", "2026-03-31 08:00", "2026-04-06 08:00"]
+        );
+    }
+
+    #[test]
+    fn ordinals_and_month_days() {
+        assert_eq!(
+            dates(
+                "DTSTART:20260101T100000 RRULE:FREQ=MONTHLY;BYDAY=2MO,-1FR;COUNT=4",
+                10
+            ),
+            [
+                "2026-01-12 10:00",
+                "2026-01-30 10:00",
+                "2026-02-09 10:00",
+                "2026-02-27 10:00"
+            ]
+        );
+        assert_eq!(
+            dates(
+                "DTSTART:20260115T100000 RRULE:FREQ=MONTHLY;BYMONTHDAY=31",
+                3
+            ),
+            ["2026-01-31 10:00", "2026-03-31 10:00", "2026-05-31 10:00"]
+        );
+        assert_eq!(
+            dates("DTSTART:20240229T100000 RRULE:FREQ=YEARLY", 2),
+            ["2024-02-29 10:00", "2028-02-29 10:00"]
+        );
+        assert_eq!(
+            dates(
+                "DTSTART:20260101T100000 RRULE:FREQ=YEARLY;BYDAY=-1SU;BYMONTH=3",
+                2
+            ),
+            ["2026-03-29 10:00", "2027-03-28 10:00"]
+        );
+    }
+
+    #[test]
+    fn until_and_exhausted_rules() {
+        assert_eq!(
+            dates(
+                "DTSTART:20261230T090000 RRULE:FREQ=DAILY;UNTIL=20270101",
+                10
+            ),
+            ["2026-12-30 09:00", "2026-12-31 09:00", "2027-01-01 09:00"]
+        );
+        assert!(dates(
+            "DTSTART:20260101T090000 RRULE:FREQ=MONTHLY;BYMONTH=2;BYMONTHDAY=30",
+            1
+        )
+        .is_empty());
+
+        // Rules like these are refused by /remindrule as they would never fire
+        let now = Utc::now();
+        for rule in [
+            "DTSTART:20190101T090000 RRULE:FREQ=DAILY;UNTIL=20200101T000000Z",
+            "DTSTART:20260101T090000 RRULE:FREQ=DAILY;COUNT=1 EXDATE:20260101T090000",
+        ] {
+            assert_eq!(rule.parse::<RRule>().unwrap().next_after(Madrid, now), None);
+        }
+    }
+
+    #[test]
+    fn leap_days_only() {
+        let rule = "DTSTART:20260101T090000 RRULE:FREQ=DAILY;BYMONTH=2;BYMONTHDAY=29";
+        assert_eq!(dates(rule, 2), ["2028-02-29 09:00", "2032-02-29 09:00"]);
+
+        // 2100 is not a leap year, so the next leap day after 2096 is 8 years later
+        let leap_days = rule.parse::<RRule>().unwrap();
+        let after = Utc.from_utc_datetime(
+            &NaiveDate::from_ymd_opt(2096, 3, 1)
+                .and_then(|d| d.and_hms_opt(0, 0, 0))
+                .unwrap(),
+        );
+        assert_eq!(
+            leap_days
+                .next_after(Madrid, after)
+                .map(|t| t.with_timezone(&Madrid).naive_local().date()),
+            NaiveDate::from_ymd_opt(2104, 2, 29)
+        );
+    }
+
+    #[test]
+    fn next_after_matches_a_full_scan() {
+        let afters = [
+            "2026-03-29T00:30:00Z",
+            "2027-06-15T09:00:00Z",
+            "2031-12-31T23:59:59Z",
+            "2040-01-01T00:00:00Z",
+        ]
+        .map(|t| t.parse::<DateTime<Utc>>().unwrap());
+        for rule in [
+            "DTSTART:19000101T090000 RRULE:FREQ=DAILY;INTERVAL=3",
+            "DTSTART:19000101T090000 RRULE:FREQ=WEEKLY;INTERVAL=2;BYDAY=TU,SU",
+            "DTSTART:19000131T090000 RRULE:FREQ=MONTHLY;BYDAY=MO,TU,WE,TH,FR;BYSETPOS=-1",
+            "DTSTART:19000101T090000 RRULE:FREQ=YEARLY;INTERVAL=5;BYDAY=-1SU;BYMONTH=3",
+            "DTSTART:20260328T020000 RRULE:FREQ=DAILY EXDATE:20270615",
+        ] {
+            let rrule = rule.parse::<RRule>().unwrap();
+            for after in afters {
+                let scanned = rrule.occurrences(Madrid).find(|t| *t > after);
+                assert_eq!(
+                    rrule.next_after(Madrid, after),
+                    scanned,
+                    "{rule} after {after}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn invalid_rules() {
+        for rule in [
+            "RRULE:FREQ=DAILY",
+            "DTSTART:20260101T090000",
+            "DTSTART:20260101T090000 RRULE:FREQ=HOURLY",
+            "DTSTART:20260101T090000 RRULE:FREQ=WEEKLY;BYDAY=1MO",
+            "DTSTART:20260101T090000 RRULE:FREQ=DAILY;COUNT=2;UNTIL=20260201",
+            "DTSTART;TZID=Europe/Madrid:20260101T090000 RRULE:FREQ=DAILY",
+            "DTSTART:20260101T090000 RRULE:FREQ=DAILY;BYHOUR=9",
+        ] {
+            assert!(rule.parse::<RRule>().is_err(), "{rule}");
+        }
+    }
+
+    #[test]
+    fn round_trips() {
+        let rrule = "dtstart:20261201T090000 freq=weekly;byday=tu"
+            .parse::<RRule>()
+            .unwrap();
+        assert_eq!(
+            rrule.to_string(),
+            "DTSTART:20261201T090000\nRRULE:FREQ=WEEKLY;BYDAY=TU"
+        );
+        assert_eq!(rrule.to_string().parse::<RRule>(), Ok(rrule));
+    }
+}
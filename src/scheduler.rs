@@ -0,0 +1,227 @@
+use ahash::AHasher;
+use chrono::{DateTime, Utc};
+use serenity::model::id::ChannelId;
+use slotmap::DefaultKey;
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap},
+    hash::BuildHasherDefault,
+    sync::Mutex,
+};
+use tokio::{sync::Notify, time::sleep};
+
+/// Identifies a reminder across all channels
+pub type ReminderId = (ChannelId, DefaultKey);
+
+#[derive(Default)]
+struct Queue {
+    heap: BinaryHeap<Reverse<(DateTime<Utc>, ReminderId)>>,
+    // The heap cannot remove arbitrary entries, so cancelled and rescheduled
+    // entries stay in it and are skipped when their time in the heap no longer
+    // matches the time planned for their reminder
+    planned: HashMap<ReminderId, DateTime<Utc>, BuildHasherDefault<AHasher>>,
+}
+
+impl Queue {
+    fn is_stale(&self, at: DateTime<Utc>, id: ReminderId) -> bool {
+        self.planned.get(&id) != Some(&at)
+    }
+
+    fn pop_stale(&mut self) {
+        while let Some(Reverse((at, id))) = self.heap.peek() {
+            if !self.is_stale(*at, *id) {
+                break;
+            }
+            self.heap.pop();
+        }
+    }
+
+    // Rebuilds the heap once stale entries dominate it, so that frequent
+    // rescheduling cannot make it grow without bound
+    fn compact(&mut self) {
+        if self.heap.len() > 64 && self.heap.len() > 2 * self.planned.len() {
+            self.heap = self
+                .planned
+                .iter()
+                .map(|(id, at)| Reverse((*at, *id)))
+                .collect();
+        }
+    }
+}
+
+/// Timer queue holding the next fire time of every active reminder
+///
+/// A single task waits on [`Scheduler::next_due`], which sleeps until the
+/// earliest planned time or until the queue changes
+#[derive(Default)]
+pub struct Scheduler {
+    queue: Mutex<Queue>,
+    changed: Notify,
+}
+
+impl Scheduler {
+    fn queue(&self) -> std::sync::MutexGuard<'_, Queue> {
+        self.queue.lock().expect("Scheduler queue poisoned")
+    }
+
+    /// Plans a reminder to fire at the given time, replacing any previous plan
+    pub fn schedule(&self, id: ReminderId, at: DateTime<Utc>) {
+        {
+            let mut queue = self.queue();
+            queue.planned.insert(id, at);
+            queue.heap.push(Reverse((at, id)));
+            queue.compact();
+        }
+        self.changed.notify_one();
+    }
+
+    /// Stops a reminder from firing until it is scheduled again
+    pub fn cancel(&self, id: ReminderId) {
+        {
+            let mut queue = self.queue();
+            queue.planned.remove(&id);
+            queue.compact();
+        }
+        self.changed.notify_one();
+    }
+
+    /// Time a reminder is planned to fire at, if any
+    pub fn planned(&self, id: ReminderId) -> Option<DateTime<Utc>> {
+        self.queue().planned.get(&id).copied()
+    }
+
+    /// Waits for the next reminder to become due and removes it from the queue
+    pub async fn next_due(&self) -> (ReminderId, DateTime<Utc>) {
+        loop {
+            // Registering interest before looking at the queue means a change made
+            // between the check and the wait still wakes us up
+            let changed = self.changed.notified();
+
+            let next = {
+                let mut queue = self.queue();
+                queue.pop_stale();
+                queue.heap.peek().map(|Reverse(entry)| *entry)
+            };
+
+            match next {
+                Some((at, id)) => {
+                    // We ensure chrono::Duration::to_std cannot panic by checking that
+                    // the duration is positive
+                    let remaining = at.signed_duration_since(Utc::now());
+                    if remaining > chrono::Duration::zero() {
+                        tokio::select! {
+                            () = sleep(remaining.to_std().unwrap()) => (),
+                            () = changed => (),
+                        }
+                        continue;
+                    }
+
+                    let mut queue = self.queue();
+                    // The entry may have been rescheduled while the lock was released
+                    if queue.heap.peek() == Some(&Reverse((at, id))) && !queue.is_stale(at, id) {
+                        queue.heap.pop();
+                        queue.planned.remove(&id);
+                        return (id, at);
+                    }
+                }
+                None => changed.await,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+    use slotmap::SlotMap;
+    use std::{sync::Arc, time::Duration as StdDuration};
+    use tokio::time::timeout;
+
+    fn ids(n: usize) -> Vec<ReminderId> {
+        let mut keys = SlotMap::new();
+        (0..n).map(|_| (ChannelId(1), keys.insert(()))).collect()
+    }
+
+    #[test]
+    fn plans_replace_each_other() {
+        let scheduler = Scheduler::default();
+        let [a, b] = ids(2)[..] else { unreachable!() };
+        let now = Utc::now();
+
+        scheduler.schedule(a, now);
+        scheduler.schedule(b, now);
+        assert_eq!(scheduler.planned(a), Some(now));
+
+        scheduler.schedule(a, now + Duration::hours(1));
+        assert_eq!(scheduler.planned(a), Some(now + Duration::hours(1)));
+        scheduler.cancel(b);
+        assert_eq!(scheduler.planned(b), None);
+        // Cancelling what is not planned is fine
+        scheduler.cancel(b);
+    }
+
+    #[tokio::test]
+    async fn due_reminders_come_in_order_without_stale_entries() {
+        let scheduler = Scheduler::default();
+        let [a, b, c, d] = ids(4)[..] else {
+            unreachable!()
+        };
+        let now = Utc::now();
+
+        scheduler.schedule(c, now - Duration::minutes(1));
+        scheduler.schedule(a, now - Duration::minutes(5));
+        scheduler.schedule(b, now - Duration::minutes(10));
+        scheduler.schedule(d, now - Duration::minutes(3));
+        // Leaves stale entries for b and d behind in the heap
+        scheduler.schedule(b, now - Duration::minutes(2));
+        scheduler.cancel(d);
+
+        for expected in [
+            (a, now - Duration::minutes(5)),
+            (b, now - Duration::minutes(2)),
+            (c, now - Duration::minutes(1)),
+        ] {
+            assert_eq!(scheduler.next_due().await, expected);
+            assert_eq!(scheduler.planned(expected.0), None);
+        }
+        assert!(scheduler.queue().heap.is_empty());
+    }
+
+    #[test]
+    fn rescheduling_does_not_grow_the_heap() {
+        let scheduler = Scheduler::default();
+        let [a, b] = ids(2)[..] else { unreachable!() };
+        let now = Utc::now();
+
+        scheduler.schedule(b, now);
+        for i in 0..1000 {
+            scheduler.schedule(a, now + Duration::seconds(i));
+        }
+        let queue = scheduler.queue();
+        assert!(queue.heap.len() <= 65, "{}", queue.heap.len());
+        assert_eq!(queue.planned.len(), 2);
+        assert_eq!(queue.planned[&a], now + Duration::seconds(999));
+    }
+
+    #[tokio::test]
+    async fn wakes_up_for_earlier_reminders() {
+        let scheduler = Arc::new(Scheduler::default());
+        let [late, early] = ids(2)[..] else {
+            unreachable!()
+        };
+        let now = Utc::now();
+        scheduler.schedule(late, now + Duration::hours(1));
+
+        let waiting = tokio::spawn({
+            let scheduler = Arc::clone(&scheduler);
+            async move { scheduler.next_due().await }
+        });
+        tokio::task::yield_now().await;
+        scheduler.schedule(early, now);
+
+        let due = timeout(StdDuration::from_secs(5), waiting).await;
+        assert_eq!(due.unwrap().unwrap(), (early, now));
+        assert_eq!(scheduler.planned(late), Some(now + Duration::hours(1)));
+    }
+}
@@ -0,0 +1,127 @@
+use super::{Delivery, SendError, Sink};
+use crate::{reminder::Target, snooze};
+use serenity::{
+    async_trait, http::error::Error as HttpError, model::application::component::ButtonStyle,
+    prelude::*, Error as SerenityError,
+};
+use std::sync::Arc;
+
+// Discord error codes for sends that keep failing until someone changes the
+// channel's permissions
+const MISSING_ACCESS: isize = 50001;
+const MISSING_PERMISSIONS: isize = 50013;
+
+// Errors that retrying cannot fix: missing permissions and any other rejected
+// request apart from rate limits
+fn is_permanent(why: &SerenityError) -> bool {
+    match why {
+        SerenityError::Http(http) => match http.as_ref() {
+            HttpError::UnsuccessfulRequest(response) => {
+                matches!(response.error.code, MISSING_ACCESS | MISSING_PERMISSIONS)
+                    || (response.status_code.is_client_error()
+                        && response.status_code.as_u16() != 429)
+            }
+            _ => false,
+        },
+        SerenityError::Model(_) => true,
+        _ => false,
+    }
+}
+
+/// Sends reminders as Discord messages
+pub struct DiscordSink {
+    ctx: Arc<Context>,
+}
+
+impl DiscordSink {
+    pub fn new(ctx: Arc<Context>) -> Self {
+        Self { ctx }
+    }
+
+    // Sends a reminder message with buttons to snooze it and, for reminders that nag
+    // until acknowledged, a button to stop them
+    async fn remind_at(&self, delivery: &Delivery, msg: &str) -> serenity::Result<()> {
+        let Delivery {
+            key,
+            mentions,
+            embed,
+            ..
+        } = delivery;
+        let channel_id = match delivery.target {
+            None => delivery.channel_id,
+            Some(Target::Channel(channel_id)) => channel_id,
+            Some(Target::Dm(user_id)) => user_id.create_dm_channel(&self.ctx).await?.id,
+        };
+        // Mentions inside embeds never ping, so they stay in the content
+        let content = match (embed, mentions.is_empty()) {
+            (Some(_), _) => mentions.to_string(),
+            (None, true) => msg.to_string(),
+            (None, false) => format!("{mentions} {msg}"),
+        };
+
+        channel_id
+            .send_message(&self.ctx, |m| {
+                if !content.is_empty() {
+                    m.content(content);
+                }
+                if let Some(embed) = embed {
+                    m.embed(|e| embed.render(e, msg, &delivery.footer, delivery.at));
+                }
+                // Mentions typed into a message never ping, users and roles only do
+                // if they were picked for the reminder
+                m.allowed_mentions(|am| {
+                    am.empty_parse()
+                        .users(mentions.users.iter().copied())
+                        .roles(mentions.roles.iter().copied())
+                })
+                .components(|comps| {
+                    if delivery.nagging {
+                        comps.create_action_row(|ar| {
+                            ar.create_button(|b| {
+                                b.style(ButtonStyle::Success)
+                                    .label("Done")
+                                    .custom_id(format!("done-{key}"))
+                            })
+                        });
+                    }
+                    comps.create_action_row(|ar| {
+                        delivery
+                            .snooze
+                            .iter()
+                            .fold(ar, |ar, mins| {
+                                ar.create_button(|b| {
+                                    b.style(ButtonStyle::Secondary)
+                                        .label(snooze::label(*mins))
+                                        .custom_id(format!("snooze-{mins}-{key}"))
+                                })
+                            })
+                            .create_button(|b| {
+                                b.style(ButtonStyle::Secondary)
+                                    .label("Snooze...")
+                                    .custom_id(format!("snooze-custom-{key}"))
+                            })
+                    })
+                })
+            })
+            .await?;
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Sink for DiscordSink {
+    fn name(&self) -> &'static str {
+        "Discord"
+    }
+
+    async fn send(&self, delivery: &Delivery, msg: &str) -> Result<(), SendError> {
+        self.remind_at(delivery, msg).await.map_err(|why| {
+            if is_permanent(&why) {
+                SendError::Permanent(why.to_string())
+            } else {
+                SendError::Transient(why.to_string())
+            }
+        })
+    }
+}
@@ -0,0 +1,102 @@
+use crate::reminder::{Embed, Mentions, Target};
+use chrono::{DateTime, Utc};
+use serenity::{async_trait, model::id::ChannelId};
+use std::{fmt, time::Duration as StdDuration};
+use tokio::time::sleep;
+
+mod discord;
+mod webhook;
+
+pub use discord::DiscordSink;
+pub use webhook::{resolve, WebhookSink, SIGNATURE_HEADER};
+
+/// Where and how a reminder's messages are sent
+pub struct Delivery {
+    /// Channel the reminder is stored in
+    pub channel_id: ChannelId,
+    pub target: Option<Target>,
+    /// Id of the reminder as a number, for systems outside of Discord
+    pub reminder_id: u64,
+    /// Serialized key of the reminder, carried by the buttons of its messages
+    pub key: String,
+    pub mentions: Mentions,
+    pub nagging: bool,
+    pub snooze: Vec<u32>,
+    pub embed: Option<Embed>,
+    /// Footer of the embed, if the reminder is sent as one
+    pub footer: String,
+    /// Time the occurrence was scheduled for
+    pub at: DateTime<Utc>,
+    /// Number of the occurrence, starting from 1
+    pub occurrence: u32,
+}
+
+/// Reason a message could not be sent
+#[derive(Debug)]
+pub enum SendError {
+    /// Sending it again is not going to help
+    Permanent(String),
+    /// Sending it again later might work
+    Transient(String),
+}
+
+impl fmt::Display for SendError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Permanent(why) | Self::Transient(why) => write!(f, "{why}"),
+        }
+    }
+}
+
+/// Somewhere reminder messages can be sent to
+#[async_trait]
+pub trait Sink: Send + Sync {
+    /// Name of the sink, shown next to the errors it runs into
+    fn name(&self) -> &'static str;
+
+    async fn send(&self, delivery: &Delivery, msg: &str) -> Result<(), SendError>;
+}
+
+/// How sends that failed are attempted again, the delay between attempts doubles
+/// every time
+#[derive(Debug, Clone, Copy)]
+pub struct Retry {
+    /// Attempts made before giving up, including the first one
+    pub attempts: u32,
+    pub delay: StdDuration,
+}
+
+impl Default for Retry {
+    fn default() -> Self {
+        Self {
+            attempts: 5,
+            delay: StdDuration::from_secs(1),
+        }
+    }
+}
+
+/// Sends a message to a sink, retrying errors that might go away
+pub async fn deliver(
+    sink: &dyn Sink,
+    delivery: &Delivery,
+    msg: &str,
+    retry: Retry,
+) -> Result<(), String> {
+    let mut attempt = 1;
+    let mut delay = retry.delay;
+    loop {
+        match sink.send(delivery, msg).await {
+            Ok(()) => return Ok(()),
+            Err(SendError::Transient(why)) if attempt < retry.attempts => {
+                println!(
+                    "Cannot send reminder to {} (attempt {attempt}), retrying: {why}",
+                    sink.name()
+                );
+                sleep(delay).await;
+                attempt += 1;
+                delay *= 2;
+            }
+            Err(why) => return Err(format!("{}: {why}", sink.name())),
+        }
+    }
+}
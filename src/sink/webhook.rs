@@ -0,0 +1,379 @@
+use super::{Delivery, SendError, Sink};
+use crate::reminder::Webhook;
+use chrono::{DateTime, Utc};
+use reqwest::{header::CONTENT_TYPE, redirect::Policy, Client, StatusCode, Url};
+use ring::hmac;
+use serde::Serialize;
+use serenity::async_trait;
+use std::{
+    fmt::Write,
+    net::{IpAddr, SocketAddr},
+    time::Duration as StdDuration,
+};
+use tokio::net::lookup_host;
+
+/// Header carrying the HMAC-SHA256 of the request body, as `sha256=<hex>`
+pub const SIGNATURE_HEADER: &str = "X-Metis-Signature";
+// Endpoints that take longer than this are treated as down
+const TIMEOUT: StdDuration = StdDuration::from_secs(10);
+
+// Addresses webhooks cannot point at: the bot's own host, private networks and
+// link-local ones like cloud metadata services
+fn is_internal(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_multicast()
+                || ip.is_documentation()
+                // Shared address space of carrier-grade NATs
+                || (a == 100 && (64..128).contains(&b))
+        }
+        IpAddr::V6(ip) => {
+            let first = ip.segments()[0];
+            ip.is_loopback()
+                || ip.is_unspecified()
+                || ip.is_multicast()
+                // Unique local and link-local addresses
+                || (first & 0xfe00) == 0xfc00
+                || (first & 0xffc0) == 0xfe80
+                || ip.to_ipv4_mapped().is_some_and(|ip| is_internal(ip.into()))
+        }
+    }
+}
+
+/// Resolves the host of a webhook URL, refusing anything but HTTP(S) URLs whose
+/// host only has public addresses. Lookups that fail are worth trying again
+pub async fn resolve(url: &str) -> Result<(Url, SocketAddr), SendError> {
+    let url = Url::parse(url).map_err(|why| SendError::Permanent(why.to_string()))?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(SendError::Permanent("not an http or https URL".to_string()));
+    }
+    let (Some(host), Some(port)) = (url.host_str(), url.port_or_known_default()) else {
+        return Err(SendError::Permanent("URL has no host".to_string()));
+    };
+
+    // IPv6 hosts are written in brackets
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    let addrs = lookup_host((host, port))
+        .await
+        .map_err(|why| SendError::Transient(format!("cannot resolve {host}: {why}")))?
+        .collect::<Vec<_>>();
+    if addrs.iter().any(|addr| is_internal(addr.ip())) {
+        return Err(SendError::Permanent(format!(
+            "{host} has a private or local address"
+        )));
+    }
+
+    match addrs.first() {
+        Some(addr) => Ok((url, *addr)),
+        None => Err(SendError::Transient(format!("{host} has no addresses"))),
+    }
+}
+
+/// Body of the requests sent to webhooks
+#[derive(Serialize)]
+struct Payload<'a> {
+    reminder_id: u64,
+    /// Channel the reminder was created in, as a string like everywhere in Discord
+    channel_id: String,
+    message: &'a str,
+    scheduled_at: DateTime<Utc>,
+    occurrence: u32,
+}
+
+/// Posts reminders as JSON to an HTTP endpoint, signed with the webhook's secret
+pub struct WebhookSink {
+    url: String,
+    key: hmac::Key,
+    // Whether endpoints on internal addresses are refused, only tests allow them
+    public_only: bool,
+}
+
+impl WebhookSink {
+    pub fn new(webhook: &Webhook) -> Self {
+        Self {
+            url: webhook.url.clone(),
+            key: hmac::Key::new(hmac::HMAC_SHA256, webhook.secret.as_bytes()),
+            public_only: true,
+        }
+    }
+
+    // Builds a client that connects to the address checked by `resolve` rather
+    // than looking the host up again, and that does not follow redirects elsewhere
+    async fn client(&self) -> Result<(Client, Url), SendError> {
+        let builder = Client::builder().redirect(Policy::none()).timeout(TIMEOUT);
+        if !self.public_only {
+            let url = Url::parse(&self.url).map_err(|why| SendError::Permanent(why.to_string()))?;
+            return Ok((
+                builder
+                    .build()
+                    .map_err(|why| SendError::Permanent(why.to_string()))?,
+                url,
+            ));
+        }
+
+        let (url, addr) = resolve(&self.url).await?;
+        let builder = match url.domain() {
+            Some(domain) => builder.resolve(domain, addr),
+            None => builder,
+        };
+        Ok((
+            builder
+                .build()
+                .map_err(|why| SendError::Permanent(why.to_string()))?,
+            url,
+        ))
+    }
+
+    fn sign(&self, body: &[u8]) -> String {
+        hmac::sign(&self.key, body)
+            .as_ref()
+            .iter()
+            .fold("sha256=".to_string(), |mut hex, byte| {
+                // Writing to a string cannot fail
+                let _ = write!(hex, "{byte:02x}");
+                hex
+            })
+    }
+}
+
+#[async_trait]
+impl Sink for WebhookSink {
+    fn name(&self) -> &'static str {
+        "webhook"
+    }
+
+    async fn send(&self, delivery: &Delivery, msg: &str) -> Result<(), SendError> {
+        let body = serde_json::to_vec(&Payload {
+            reminder_id: delivery.reminder_id,
+            channel_id: delivery.channel_id.to_string(),
+            message: msg,
+            scheduled_at: delivery.at,
+            occurrence: delivery.occurrence,
+        })
+        .expect("Error serializing webhook payload");
+
+        let (client, url) = self.client().await?;
+        let response = client
+            .post(url)
+            .header(CONTENT_TYPE, "application/json")
+            .header(SIGNATURE_HEADER, self.sign(&body))
+            .body(body)
+            .send()
+            .await
+            .map_err(|why| {
+                // Requests that cannot even be built never will be
+                if why.is_builder() {
+                    SendError::Permanent(why.to_string())
+                } else {
+                    SendError::Transient(why.to_string())
+                }
+            })?;
+
+        match response.status() {
+            status if status.is_success() => Ok(()),
+            status if status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS => {
+                Err(SendError::Transient(format!("endpoint answered {status}")))
+            }
+            status => Err(SendError::Permanent(format!("endpoint answered {status}"))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        reminder::Mentions,
+        sink::{deliver, Retry},
+    };
+    use hyper::{
+        service::{make_service_fn, service_fn},
+        Body, Request, Response, Server,
+    };
+    use serenity::model::id::ChannelId;
+    use std::{
+        convert::Infallible,
+        net::TcpListener,
+        sync::{Arc, Mutex},
+    };
+
+    type Received = Arc<Mutex<Vec<(Option<String>, Vec<u8>)>>>;
+
+    // Starts an endpoint answering with the given statuses in turn and then with
+    // 200, returns its URL and the signature and body of every request it got
+    fn endpoint(statuses: Vec<u16>) -> (String, Received) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let received = Received::default();
+
+        let requests = Arc::clone(&received);
+        let make_svc = make_service_fn(move |_| {
+            let requests = Arc::clone(&requests);
+            let statuses = statuses.clone();
+            async move {
+                Ok::<_, Infallible>(service_fn(move |req: Request<Body>| {
+                    let requests = Arc::clone(&requests);
+                    let statuses = statuses.clone();
+                    async move {
+                        let signature = req
+                            .headers()
+                            .get(SIGNATURE_HEADER)
+                            .and_then(|s| s.to_str().ok())
+                            .map(ToString::to_string);
+                        let body = hyper::body::to_bytes(req.into_body()).await.unwrap();
+                        let mut requests = requests.lock().unwrap();
+                        let status = statuses.get(requests.len()).copied().unwrap_or(200);
+                        requests.push((signature, body.to_vec()));
+
+                        let mut response = Response::new(Body::empty());
+                        *response.status_mut() = status.try_into().unwrap();
+                        Ok::<_, Infallible>(response)
+                    }
+                }))
+            }
+        });
+        tokio::spawn(Server::from_tcp(listener).unwrap().serve(make_svc));
+
+        (url, received)
+    }
+
+    // Sinks posting to the local test endpoints
+    fn sink(url: String) -> WebhookSink {
+        WebhookSink {
+            public_only: false,
+            ..WebhookSink::new(&Webhook {
+                url,
+                secret: "secret".to_string(),
+                discord: false,
+            })
+        }
+    }
+
+    fn delivery() -> Delivery {
+        Delivery {
+            channel_id: ChannelId(42),
+            target: None,
+            reminder_id: 7,
+            key: String::new(),
+            mentions: Mentions::default(),
+            nagging: false,
+            snooze: Vec::new(),
+            embed: None,
+            footer: String::new(),
+            at: "2026-12-01T09:30:00Z".parse().unwrap(),
+            occurrence: 3,
+        }
+    }
+
+    const RETRY: Retry = Retry {
+        attempts: 3,
+        delay: StdDuration::from_millis(1),
+    };
+
+    #[tokio::test]
+    async fn posts_signed_payloads() {
+        let (url, received) = endpoint(vec![]);
+        deliver(&sink(url), &delivery(), "deploy", RETRY)
+            .await
+            .unwrap();
+
+        let received = received.lock().unwrap();
+        assert_eq!(received.len(), 1);
+        let (signature, body) = &received[0];
+
+        let payload: serde_json::Value = serde_json::from_slice(body).unwrap();
+        assert_eq!(
+            payload,
+            serde_json::json!({
+                "reminder_id": 7,
+                "channel_id": "42",
+                "message": "deploy",
+                "scheduled_at": "2026-12-01T09:30:00Z",
+                "occurrence": 3,
+            })
+        );
+
+        let key = hmac::Key::new(hmac::HMAC_SHA256, b"secret");
+        let hex = signature
+            .as_deref()
+            .unwrap()
+            .strip_prefix("sha256=")
+            .unwrap();
+        let tag = (0..hex.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+            .collect::<Vec<_>>();
+        assert!(hmac::verify(&key, body, &tag).is_ok());
+    }
+
+    #[tokio::test]
+    async fn retries_until_the_endpoint_recovers() {
+        let (url, received) = endpoint(vec![500, 429]);
+        assert!(deliver(&sink(url), &delivery(), "deploy", RETRY)
+            .await
+            .is_ok());
+        assert_eq!(received.lock().unwrap().len(), 3);
+
+        let (url, received) = endpoint(vec![503; 5]);
+        assert!(deliver(&sink(url), &delivery(), "deploy", RETRY)
+            .await
+            .is_err());
+        assert_eq!(received.lock().unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn gives_up_on_rejected_requests() {
+        let (url, received) = endpoint(vec![404]);
+        let error = deliver(&sink(url), &delivery(), "deploy", RETRY)
+            .await
+            .unwrap_err();
+        assert!(error.starts_with("webhook: endpoint answered 404"));
+        assert_eq!(received.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn refuses_internal_addresses() {
+        for url in [
+            "ftp://example.com/hook",
+            "http://127.0.0.1:8080/hook",
+            "http://localhost/hook",
+            "http://10.0.0.1/hook",
+            "http://192.168.1.1/hook",
+            "http://169.254.169.254/latest/meta-data",
+            "http://100.64.0.1/hook",
+            "http://[::1]/hook",
+            "http://[fd00::1]/hook",
+            "http://[::ffff:127.0.0.1]/hook",
+        ] {
+            assert!(
+                matches!(resolve(url).await, Err(SendError::Permanent(_))),
+                "{url}"
+            );
+        }
+        assert!(!is_internal("93.184.216.34".parse().unwrap()));
+        assert!(!is_internal("2606:2800:220:1::".parse().unwrap()));
+
+        // Checked again when sending, in case the host changed its addresses
+        let (url, received) = endpoint(vec![]);
+        let error = deliver(
+            &WebhookSink::new(&Webhook {
+                url,
+                secret: "secret".to_string(),
+                discord: false,
+            }),
+            &delivery(),
+            "deploy",
+            RETRY,
+        )
+        .await
+        .unwrap_err();
+        assert!(error.contains("private or local address"));
+        assert!(received.lock().unwrap().is_empty());
+    }
+}
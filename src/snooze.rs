@@ -0,0 +1,366 @@
+use crate::{
+    manager::{self, Manager},
+    reminder::{Embed, Mentions, Nag, Reminder, ReminderType, Target, MAX_SNOOZE_MINS},
+    time_parser,
+};
+use chrono::{DateTime, Duration, Utc};
+use serenity::{
+    builder::CreateComponents,
+    model::{
+        application::{
+            component::{ActionRowComponent, InputTextStyle},
+            interaction::{
+                message_component::MessageComponentInteraction, modal::ModalSubmitInteraction,
+                InteractionResponseType,
+            },
+        },
+        channel::Message,
+        id::{ChannelId, UserId},
+    },
+    prelude::*,
+};
+use slotmap::DefaultKey;
+use std::sync::Arc;
+
+/// Label of a snooze button, e.g. "+15 min", "+2 h" or "+1 d"
+pub fn label(mins: u32) -> String {
+    match mins {
+        m if m % (24 * 60) == 0 => format!("+{} d", m / (24 * 60)),
+        m if m % 60 == 0 => format!("+{} h", m / 60),
+        m => format!("+{m} min"),
+    }
+}
+
+// Creates a one-shot reminder repeating a sent one. It is built from the reminder
+// it was sent for, or from the message if that reminder was already removed
+async fn snooze(
+    manager: &Manager,
+    channel_id: ChannelId,
+    user_id: UserId,
+    message: &Message,
+    key: Option<DefaultKey>,
+    at: DateTime<Utc>,
+) {
+    let original = match key {
+        Some(key) => manager
+            .channel_data(channel_id)
+            .await
+            .and_then(|cd| cd.reminders.get(key).cloned()),
+        None => None,
+    };
+
+    let once = ReminderType::Once(at.naive_utc());
+    let reminder = if let Some(original) = original {
+        Reminder {
+            mentions: original.mentions,
+            embed: original.embed,
+            target: original.target,
+            webhook: original.webhook,
+            nag: original
+                .nag
+                .map(|nag| Nag::new(nag.every, nag.max_attempts)),
+            ..Reminder::new(once, original.msg)
+        }
+    } else {
+        from_message(message, channel_id, once)
+    };
+
+    // Snoozing takes care of the reminder for now, so it stops nagging
+    if let Some(key) = key {
+        manager.acknowledge(channel_id, key).await;
+    }
+    manager
+        .add_reminder(
+            channel_id,
+            Reminder {
+                author: Some(user_id),
+                snoozed_from: key,
+                ..reminder
+            },
+        )
+        .await;
+}
+
+// Rebuilds a reminder from a message it sent, for snoozes of reminders that were
+// removed since. Messages do not carry the URL and secret of a webhook the
+// reminder was posted to as well, so the snooze is only sent to Discord
+fn from_message(message: &Message, channel_id: ChannelId, once: ReminderType) -> Reminder {
+    // Reminders sent as embeds only have their mentions in the content
+    let (mentions, msg, embed) = if let Some(embed) = message.embeds.first() {
+        (
+            Mentions::split(&format!("{} ", message.content)).0,
+            embed.description.clone().unwrap_or_default(),
+            Some(Embed {
+                title: embed.title.clone(),
+                colour: embed.colour.map(|colour| colour.0),
+            }),
+        )
+    } else {
+        let (mentions, msg) = Mentions::split(&message.content);
+        (mentions, msg.to_string(), None)
+    };
+
+    Reminder {
+        mentions,
+        embed,
+        // It goes back to wherever the message was sent
+        target: (message.channel_id != channel_id).then_some(Target::Channel(message.channel_id)),
+        ..Reminder::new(once, msg)
+    }
+}
+
+// Copies the buttons of a message, all of them disabled
+fn disable<'a>(comps: &'a mut CreateComponents, message: &Message) -> &'a mut CreateComponents {
+    for row in &message.components {
+        comps.create_action_row(|ar| {
+            for component in &row.components {
+                if let ActionRowComponent::Button(button) = component {
+                    ar.create_button(|b| {
+                        b.style(button.style)
+                            .label(button.label.clone().unwrap_or_default())
+                            .custom_id(button.custom_id.clone().unwrap_or_default())
+                            .disabled(true)
+                    });
+                }
+            }
+            ar
+        });
+    }
+
+    comps
+}
+
+// Notes on the original message until when it was snoozed
+fn snoozed_content(message: &Message, user_id: UserId, at: DateTime<Utc>) -> String {
+    format!(
+        "{}\n*(snoozed until <t:{}:f> by <@{user_id}>)*",
+        message.content,
+        at.timestamp()
+    )
+}
+
+/// Handles the snooze buttons of sent reminders, custom ids are
+/// `snooze-{mins}-{key}` and `snooze-custom-{key}`
+///
+/// Messages sent before snoozes were linked to their reminder have
+/// `postpone-{mins}` buttons, which are snoozed from the message alone
+pub async fn handle(ctx: Arc<Context>, manager: &Manager, message: &MessageComponentInteraction) {
+    let custom_id = &message.data.custom_id;
+    // This should never panic because we generated these custom_ids
+    let (mins, key_str) = match custom_id.strip_prefix("postpone-") {
+        Some(mins) => (mins, None),
+        None => custom_id
+            .strip_prefix("snooze-")
+            .and_then(|id| id.split_once('-'))
+            .map(|(mins, key)| (mins, Some(key)))
+            .unwrap(),
+    };
+
+    if mins == "custom" {
+        // This should never panic, custom snoozes always carry a key
+        open_modal(&ctx, message, key_str.unwrap()).await;
+        return;
+    }
+
+    let (channel_id, key) = match key_str {
+        Some(key) => manager::button_key(key, message.channel_id),
+        None => (message.channel_id, None),
+    };
+    let mins: u32 = mins.parse().expect("Error parsing snooze duration");
+    let at = Utc::now() + Duration::minutes(mins.min(MAX_SNOOZE_MINS).into());
+    snooze(
+        manager,
+        channel_id,
+        message.user.id,
+        &message.message,
+        key,
+        at,
+    )
+    .await;
+
+    let content = snoozed_content(&message.message, message.user.id, at);
+    if let Err(why) = message
+        .create_interaction_response(&ctx.http, |response| {
+            response
+                .kind(InteractionResponseType::UpdateMessage)
+                .interaction_response_data(|data| {
+                    data.content(content)
+                        .allowed_mentions(|am| am.empty_parse())
+                        .components(|comps| disable(comps, &message.message))
+                })
+        })
+        .await
+    {
+        println!("Cannot respond to component interaction: {why:#?}");
+    }
+}
+
+// Asks when to send the reminder again
+async fn open_modal(ctx: &Context, message: &MessageComponentInteraction, key: &str) {
+    if let Err(why) = message
+        .create_interaction_response(&ctx.http, |response| {
+            response
+                .kind(InteractionResponseType::Modal)
+                .interaction_response_data(|modal| {
+                    modal
+                        .custom_id(format!("snooze-{key}"))
+                        .title("Snooze reminder")
+                        .components(|comps| {
+                            comps.create_action_row(|ar| {
+                                ar.create_input_text(|it| {
+                                    it.custom_id("when")
+                                        .label("Send it again")
+                                        .placeholder("e.g. in 2h, tomorrow at 9am")
+                                        .style(InputTextStyle::Short)
+                                        .required(true)
+                                })
+                            })
+                        })
+                })
+        })
+        .await
+    {
+        println!("Cannot respond to component interaction: {why:#?}");
+    }
+}
+
+/// Snoozes a reminder until the time submitted through the custom snooze modal
+pub async fn handle_custom(ctx: Arc<Context>, manager: &Manager, submit: &ModalSubmitInteraction) {
+    let (channel_id, key) = manager::button_key(
+        submit.data.custom_id.trim_start_matches("snooze-"),
+        submit.channel_id,
+    );
+    let when = submit
+        .data
+        .components
+        .iter()
+        .flat_map(|ar| &ar.components)
+        .find_map(|c| match c {
+            ActionRowComponent::InputText(it) if it.custom_id == "when" => Some(it.value.trim()),
+            _ => None,
+        })
+        .unwrap_or_default();
+
+    let now = Utc::now();
+    let tz = manager.user_tz(submit.user.id, channel_id).await;
+    let at = time_parser::parse(when, tz, now).and_then(|at| {
+        if at - now > Duration::minutes(MAX_SNOOZE_MINS.into()) {
+            Err("reminders can be snoozed for a week at most")
+        } else {
+            Ok(at)
+        }
+    });
+
+    let result = match (&submit.message, at) {
+        (Some(message), Ok(at)) => {
+            snooze(manager, channel_id, submit.user.id, message, key, at).await;
+            Ok((snoozed_content(message, submit.user.id, at), message))
+        }
+        (None, _) => Err("the reminder message no longer exists"),
+        (_, Err(why)) => Err(why),
+    };
+
+    if let Err(why) = submit
+        .create_interaction_response(&ctx.http, |response| match result {
+            Ok((content, message)) => response
+                .kind(InteractionResponseType::UpdateMessage)
+                .interaction_response_data(|data| {
+                    data.content(content)
+                        .allowed_mentions(|am| am.empty_parse())
+                        .components(|comps| disable(comps, message))
+                }),
+            Err(why) => {
+                response.interaction_response_data(|data| data.content(why).ephemeral(true))
+            }
+        })
+        .await
+    {
+        println!("Cannot respond to modal submission: {why:#?}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn labels_durations() {
+        assert_eq!(label(15), "+15 min");
+        assert_eq!(label(90), "+90 min");
+        assert_eq!(label(120), "+2 h");
+        assert_eq!(label(2 * 24 * 60), "+2 d");
+    }
+
+    fn message(channel_id: u64, content: &str, embeds: &serde_json::Value) -> Message {
+        serde_json::from_value(serde_json::json!({
+            "id": "10",
+            "channel_id": channel_id.to_string(),
+            "author": { "id": "20", "username": "Metis", "discriminator": "0000", "avatar": null },
+            "content": content,
+            "timestamp": "2026-12-01T09:00:00Z",
+            "edited_timestamp": null,
+            "tts": false,
+            "mention_everyone": false,
+            "mentions": [],
+            "mention_roles": [],
+            "attachments": [],
+            "embeds": embeds,
+            "pinned": false,
+            "type": 0
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn rebuilds_removed_reminders_from_their_message() {
+        let once = || ReminderType::Once(Utc::now().naive_utc());
+
+        let plain = from_message(
+            &message(1, "<@1> stand up", &serde_json::json!([])),
+            ChannelId(1),
+            once(),
+        );
+        assert_eq!(plain.msg, "stand up");
+        assert_eq!(plain.mentions.users, vec![UserId(1)]);
+        assert_eq!(plain.embed, None);
+        assert_eq!(plain.target, None);
+
+        let embedded = from_message(
+            &message(
+                2,
+                "<@1>",
+                &serde_json::json!([{
+                    "type": "rich",
+                    "title": "Standup",
+                    "description": "stand up",
+                    "color": 0x00ff_8800,
+                    "fields": []
+                }]),
+            ),
+            ChannelId(1),
+            once(),
+        );
+        assert_eq!(embedded.msg, "stand up");
+        assert_eq!(embedded.mentions.users, vec![UserId(1)]);
+        assert_eq!(
+            embedded.embed,
+            Some(Embed {
+                title: Some("Standup".to_string()),
+                colour: Some(0x00ff_8800),
+            })
+        );
+        assert_eq!(embedded.target, Some(Target::Channel(ChannelId(2))));
+    }
+
+    #[test]
+    fn splits_mentions() {
+        let (mentions, msg) = Mentions::split("<@1> <@&2> stand up <@3>");
+        assert_eq!(mentions.users, vec![UserId(1)]);
+        assert_eq!(mentions.roles, vec![serenity::model::id::RoleId(2)]);
+        assert_eq!(msg, "stand up <@3>");
+
+        let (mentions, msg) = Mentions::split("no <@1> pings");
+        assert!(mentions.is_empty());
+        assert_eq!(msg, "no <@1> pings");
+    }
+}
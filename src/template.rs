@@ -0,0 +1,172 @@
+use chrono::{DateTime, NaiveDate};
+use chrono_tz::Tz;
+use std::str::FromStr;
+
+/// Placeholders a reminder message can contain, written between braces
+#[derive(Debug, PartialEq, Eq)]
+enum Placeholder {
+    Date,
+    Time,
+    Weekday,
+    Occurrence,
+    Next,
+    /// Days left until the date
+    Countdown(NaiveDate),
+}
+
+impl FromStr for Placeholder {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim() {
+            "date" => Ok(Self::Date),
+            "time" => Ok(Self::Time),
+            "weekday" => Ok(Self::Weekday),
+            "occurrence" => Ok(Self::Occurrence),
+            "next" => Ok(Self::Next),
+            s => match s.strip_prefix("countdown:") {
+                Some(date) => NaiveDate::parse_from_str(date.trim(), "%Y-%m-%d")
+                    .map(Self::Countdown)
+                    .map_err(|_| format!("invalid countdown date {date}, use e.g. 2026-12-24")),
+                None => Err(format!(
+                    "unknown placeholder {{{s}}}, write {{{{ and }}}} for literal braces"
+                )),
+            },
+        }
+    }
+}
+
+/// Values placeholders are expanded to for an occurrence of a reminder
+pub struct Vars {
+    /// Time of the occurrence in the reminder's timezone
+    pub at: DateTime<Tz>,
+    /// Number of the occurrence, starting from 1
+    pub occurrence: u32,
+    pub next: Option<DateTime<Tz>>,
+}
+
+impl Vars {
+    fn get(&self, placeholder: &Placeholder) -> String {
+        match placeholder {
+            Placeholder::Date => self.at.format("%Y-%m-%d").to_string(),
+            Placeholder::Time => self.at.format("%H:%M").to_string(),
+            Placeholder::Weekday => self.at.format("%A").to_string(),
+            Placeholder::Occurrence => self.occurrence.to_string(),
+            Placeholder::Next => self.next.map_or("never".to_string(), |next| {
+                next.format("%Y-%m-%d %H:%M").to_string()
+            }),
+            Placeholder::Countdown(date) => (*date - self.at.naive_local().date())
+                .num_days()
+                .max(0)
+                .to_string(),
+        }
+    }
+}
+
+// Copies the template, replacing every placeholder with what `field` returns for
+// it. Doubled braces stand for literal ones and a brace that is never closed is
+// kept as is
+fn expand<E>(
+    template: &str,
+    mut field: impl FnMut(&str) -> Result<String, E>,
+) -> Result<String, E> {
+    let mut out = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(i) = rest.find(['{', '}']) {
+        out.push_str(&rest[..i]);
+        rest = &rest[i..];
+
+        if let Some(after) = rest.strip_prefix("{{").or_else(|| rest.strip_prefix("}}")) {
+            out.push_str(&rest[..1]);
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix('}') {
+            out.push('}');
+            rest = after;
+        } else if let Some(end) = rest.find('}') {
+            out.push_str(&field(&rest[1..end])?);
+            rest = &rest[end + 1..];
+        } else {
+            break;
+        }
+    }
+    out.push_str(rest);
+
+    Ok(out)
+}
+
+/// Checks that a message only contains known placeholders
+pub fn validate(template: &str) -> Result<(), String> {
+    expand(template, |name| {
+        name.parse::<Placeholder>().map(|_| String::new())
+    })
+    .map(|_| ())
+}
+
+/// Fills in the placeholders of a message, unknown ones are left as written
+pub fn render(template: &str, vars: &Vars) -> String {
+    let expanded: Result<_, ()> = expand(template, |name| {
+        Ok(match name.parse() {
+            Ok(placeholder) => vars.get(&placeholder),
+            Err(_) => format!("{{{name}}}"),
+        })
+    });
+
+    // This should never panic, filling in placeholders cannot fail
+    expanded.expect("Error rendering template")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono_tz::Europe::Madrid;
+
+    fn madrid(rfc3339: &str) -> DateTime<Tz> {
+        DateTime::parse_from_rfc3339(rfc3339)
+            .unwrap()
+            .with_timezone(&Madrid)
+    }
+
+    fn vars() -> Vars {
+        Vars {
+            at: madrid("2026-12-01T09:30:00+01:00"),
+            occurrence: 3,
+            next: Some(madrid("2026-12-08T09:30:00+01:00")),
+        }
+    }
+
+    #[test]
+    fn renders_placeholders() {
+        assert_eq!(
+            render("{weekday} {date} {time}, #{occurrence}", &vars()),
+            "Tuesday 2026-12-01 09:30, #3"
+        );
+        assert_eq!(render("next: {next}", &vars()), "next: 2026-12-08 09:30");
+        assert_eq!(
+            render("{countdown:2026-12-24} days to go", &vars()),
+            "23 days to go"
+        );
+    }
+
+    #[test]
+    fn renders_edge_cases() {
+        let vars = Vars {
+            next: None,
+            ..vars()
+        };
+        assert_eq!(render("{next}", &vars), "never");
+        assert_eq!(render("{countdown:2026-01-01}", &vars), "0");
+        assert_eq!(render("{{date}} {}", &vars), "{date} {}");
+        assert_eq!(render("a } b { c", &vars), "a } b { c");
+        assert_eq!(render("plain message", &vars), "plain message");
+    }
+
+    #[test]
+    fn validates_placeholders() {
+        assert!(validate("{date} {time} {weekday} {occurrence} {next}").is_ok());
+        assert!(validate("{countdown:2026-12-24} {{literal}} unclosed {").is_ok());
+        assert!(validate("{dat}").is_err());
+        assert!(validate("{countdown:tomorrow}").is_err());
+        assert!(validate("{}").is_err());
+    }
+}
@@ -0,0 +1,458 @@
+use chrono::{
+    DateTime, Datelike, Duration, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc, Weekday,
+};
+use chrono_tz::Tz;
+
+const MONTHS: [&str; 12] = [
+    "january",
+    "february",
+    "march",
+    "april",
+    "may",
+    "june",
+    "july",
+    "august",
+    "september",
+    "october",
+    "november",
+    "december",
+];
+
+// Keeps durations well within the range chrono can represent
+const MAX_AMOUNT: i32 = 100_000;
+
+// Dates given without a time are sent at this hour
+const DEFAULT_HOUR: u32 = 9;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Day {
+    // Days after today
+    Offset(i64),
+    // The first such weekday after today, or today if `next` is not set and the
+    // time is still ahead
+    Weekday {
+        weekday: Weekday,
+        next: bool,
+    },
+    // A calendar date, the closest one in the future if the year is left out
+    Date {
+        month: u32,
+        day: u32,
+        year: Option<i32>,
+    },
+}
+
+const WEEKDAYS: [(&str, Weekday); 7] = [
+    ("monday", Weekday::Mon),
+    ("tuesday", Weekday::Tue),
+    ("wednesday", Weekday::Wed),
+    ("thursday", Weekday::Thu),
+    ("friday", Weekday::Fri),
+    ("saturday", Weekday::Sat),
+    ("sunday", Weekday::Sun),
+];
+
+// Full names and any abbreviation of at least three letters
+fn weekday(token: &str) -> Option<Weekday> {
+    WEEKDAYS
+        .iter()
+        .find(|(name, _)| token.len() >= 3 && name.starts_with(token))
+        .map(|(_, weekday)| *weekday)
+}
+
+fn month(token: &str) -> Option<u32> {
+    (1..)
+        .zip(MONTHS)
+        .find(|(_, name)| token.len() >= 3 && name.starts_with(token))
+        .map(|(month, _)| month)
+}
+
+// Day of the month, with or without an ordinal suffix
+fn day_of_month(token: &str) -> Option<u32> {
+    let digits = ["st", "nd", "rd", "th"]
+        .iter()
+        .find_map(|suffix| token.strip_suffix(suffix))
+        .unwrap_or(token);
+    digits.parse().ok().filter(|d| (1..=31).contains(d))
+}
+
+fn on_the_hour(hour: u32) -> Result<NaiveTime, &'static str> {
+    NaiveTime::from_hms_opt(hour, 0, 0).ok_or("invalid time")
+}
+
+// Times like "9", "9am", "9:30", "9:30pm" or "14:00", the am/pm marker may also
+// be the following token
+fn time(token: &str, following: Option<&str>) -> Option<(NaiveTime, bool)> {
+    let (clock, meridiem, used_next) = if let Some(clock) = token.strip_suffix("am") {
+        (clock, Some(false), false)
+    } else if let Some(clock) = token.strip_suffix("pm") {
+        (clock, Some(true), false)
+    } else {
+        match following {
+            Some("am") => (token, Some(false), true),
+            Some("pm") => (token, Some(true), true),
+            _ => (token, None, false),
+        }
+    };
+
+    let (hour, min) = match clock.split_once(':') {
+        Some((hour, min)) if min.len() == 2 => (hour.parse().ok()?, min.parse().ok()?),
+        Some(_) => return None,
+        None => (clock.parse().ok()?, 0),
+    };
+    let hour = match meridiem {
+        Some(_) if !(1..=12).contains(&hour) => return None,
+        Some(pm) => hour % 12 + if pm { 12 } else { 0 },
+        None => hour,
+    };
+
+    Some((NaiveTime::from_hms_opt(hour, min, 0)?, used_next))
+}
+
+// Durations like "2h30m", "2 hours 30 minutes", "1 day" or "a week"
+fn duration(tokens: &[&str]) -> Result<Duration, &'static str> {
+    // Splitting numbers from units turns every form into alternating amounts and units
+    let mut parts = Vec::new();
+    for token in tokens {
+        let mut rest = *token;
+        while !rest.is_empty() {
+            let split = if rest.starts_with(|c: char| c.is_ascii_digit()) {
+                rest.find(|c: char| !c.is_ascii_digit())
+            } else {
+                rest.find(|c: char| c.is_ascii_digit())
+            }
+            .unwrap_or(rest.len());
+            parts.push(&rest[..split]);
+            rest = &rest[split..];
+        }
+    }
+
+    let mut total = Duration::zero();
+    let mut parts = parts.into_iter().filter(|p| *p != "and");
+    while let Some(amount) = parts.next() {
+        let amount = match amount {
+            "a" | "an" => 1,
+            _ => amount
+                .parse::<i32>()
+                .map_err(|_| "expected an amount of time")?,
+        };
+        if amount > MAX_AMOUNT {
+            return Err("amount of time too large");
+        }
+        let unit = match parts.next().ok_or("missing unit of time")? {
+            "m" | "min" | "mins" | "minute" | "minutes" => Duration::minutes(1),
+            "h" | "hr" | "hrs" | "hour" | "hours" => Duration::hours(1),
+            "d" | "day" | "days" => Duration::days(1),
+            "w" | "week" | "weeks" => Duration::weeks(1),
+            _ => return Err("unknown unit of time"),
+        };
+        total = total
+            .checked_add(&(unit * amount))
+            .ok_or("amount of time too large")?;
+    }
+
+    if total > Duration::zero() {
+        Ok(total)
+    } else {
+        Err("expected an amount of time")
+    }
+}
+
+/// Resolves a local time, times skipped by a DST change are moved forward by
+/// the change and times repeated by one resolve to their first occurrence
+pub fn to_utc(tz: Tz, local: NaiveDateTime) -> Option<DateTime<Utc>> {
+    tz.from_local_datetime(&local)
+        .earliest()
+        .or_else(|| {
+            tz.from_local_datetime(&(local + Duration::hours(1)))
+                .earliest()
+        })
+        .map(|datetime| datetime.with_timezone(&Utc))
+}
+
+/// Resolves expressions like "in 2h30m", "tomorrow at 9am", "next friday",
+/// "dec 1 2pm" or "2026-12-01 14:00" in the given timezone
+pub fn parse(input: &str, tz: Tz, now: DateTime<Utc>) -> Result<DateTime<Utc>, &'static str> {
+    let input = input.to_lowercase().replace(',', " ");
+    let tokens = input.split_whitespace().collect::<Vec<_>>();
+
+    if let Some(("in", rest)) = tokens.split_first().map(|(first, rest)| (*first, rest)) {
+        return now
+            .checked_add_signed(duration(rest)?)
+            .ok_or("amount of time too large");
+    }
+
+    let mut day = None;
+    let mut clock = None;
+    let mut set_day = |d| day.replace(d).map_or(Ok(()), |_| Err("more than one date"));
+    let mut set_time = |t| {
+        clock
+            .replace(t)
+            .map_or(Ok(()), |_| Err("more than one time"))
+    };
+
+    let mut i = 0;
+    while i < tokens.len() {
+        let token = tokens[i];
+        let following = tokens.get(i + 1).copied();
+        i += 1;
+
+        match token {
+            "at" | "on" => (),
+            "today" => set_day(Day::Offset(0))?,
+            "tomorrow" => set_day(Day::Offset(1))?,
+            "noon" | "midday" => set_time(on_the_hour(12)?)?,
+            "midnight" => set_time(on_the_hour(0)?)?,
+            "next" | "this" => {
+                let weekday = following.and_then(weekday).ok_or("expected a weekday")?;
+                set_day(Day::Weekday {
+                    weekday,
+                    next: token == "next",
+                })?;
+                i += 1;
+            }
+            _ => {
+                if let Some(weekday) = weekday(token) {
+                    set_day(Day::Weekday {
+                        weekday,
+                        next: false,
+                    })?;
+                } else if let Ok(date) = NaiveDate::parse_from_str(token, "%Y-%m-%d") {
+                    set_day(Day::Date {
+                        month: date.month(),
+                        day: date.day(),
+                        year: Some(date.year()),
+                    })?;
+                } else if token.contains('/') {
+                    // 01/12 is January 12th or December 1st depending on who you ask
+                    return Err("dates with slashes are ambiguous, use 2026-12-01 or dec 1");
+                } else if let Some(month) = month(token) {
+                    // "dec 1" and "dec 1st 2026"
+                    let day = following.and_then(day_of_month).ok_or("expected a day")?;
+                    i += 1;
+                    let year = tokens
+                        .get(i)
+                        .filter(|y| y.len() == 4)
+                        .and_then(|y| y.parse().ok());
+                    if year.is_some() {
+                        i += 1;
+                    }
+                    set_day(Day::Date { month, day, year })?;
+                } else if let (Some(day), Some(month)) =
+                    (day_of_month(token), following.and_then(month))
+                {
+                    // "1 dec"
+                    i += 1;
+                    set_day(Day::Date {
+                        month,
+                        day,
+                        year: None,
+                    })?;
+                } else if let Some((time, used_next)) = time(token, following) {
+                    set_time(time)?;
+                    if used_next {
+                        i += 1;
+                    }
+                } else {
+                    return Err("could not understand the time");
+                }
+            }
+        }
+    }
+
+    resolve(day, clock, tz, now)
+}
+
+fn resolve(
+    day: Option<Day>,
+    clock: Option<NaiveTime>,
+    tz: Tz,
+    now: DateTime<Utc>,
+) -> Result<DateTime<Utc>, &'static str> {
+    let today = now.with_timezone(&tz).naive_local().date();
+    let at = |date: NaiveDate, time: NaiveTime| to_utc(tz, date.and_time(time));
+    let time = match clock {
+        Some(time) => time,
+        None => on_the_hour(DEFAULT_HOUR)?,
+    };
+
+    let resolved = match day {
+        None if clock.is_none() => return Err("no time given"),
+        // A time on its own is the next time the clock shows it
+        None => at(today, time)
+            .filter(|t| *t > now)
+            .or_else(|| at(today + Duration::days(1), time)),
+        Some(Day::Offset(days)) => at(today + Duration::days(days), time),
+        Some(Day::Weekday { weekday, next }) => {
+            let ahead = i64::from(
+                (7 + weekday.num_days_from_monday() - today.weekday().num_days_from_monday()) % 7,
+            );
+            match ahead {
+                0 if next => at(today + Duration::days(7), time),
+                0 => at(today, time)
+                    .filter(|t| *t > now)
+                    .or_else(|| at(today + Duration::days(7), time)),
+                _ => at(today + Duration::days(ahead), time),
+            }
+        }
+        Some(Day::Date { month, day, year }) => match year {
+            Some(year) => NaiveDate::from_ymd_opt(year, month, day)
+                .ok_or("no such date")
+                .map(|date| at(date, time))?,
+            None => [today.year(), today.year() + 1]
+                .into_iter()
+                .filter_map(|year| NaiveDate::from_ymd_opt(year, month, day))
+                .filter_map(|date| at(date, time))
+                .find(|t| *t > now),
+        },
+    };
+
+    match resolved {
+        Some(t) if t > now => Ok(t),
+        Some(_) => Err("that time has already passed"),
+        None => Err("no such date"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono_tz::{America::New_York, Europe::Madrid};
+
+    fn utc(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    // Wednesday 2026-03-25 10:00 in Madrid (UTC+1)
+    fn now() -> DateTime<Utc> {
+        utc("2026-03-25T09:00:00Z")
+    }
+
+    fn madrid(input: &str) -> Result<String, &'static str> {
+        parse(input, Madrid, now()).map(|t| t.with_timezone(&Madrid).to_rfc3339())
+    }
+
+    #[test]
+    fn relative() {
+        assert_eq!(
+            parse("in 2h30m", Madrid, now()),
+            Ok(utc("2026-03-25T11:30:00Z"))
+        );
+        assert_eq!(
+            parse("in 1 day and 2 hours", Madrid, now()),
+            Ok(utc("2026-03-26T11:00:00Z"))
+        );
+        assert_eq!(
+            parse("in a week", Madrid, now()),
+            Ok(utc("2026-04-01T09:00:00Z"))
+        );
+        assert_eq!(
+            parse("in 90 mins", Madrid, now()),
+            Ok(utc("2026-03-25T10:30:00Z"))
+        );
+        assert!(parse("in soon", Madrid, now()).is_err());
+        assert!(parse("in 5", Madrid, now()).is_err());
+        assert!(parse("in 0m", Madrid, now()).is_err());
+    }
+
+    #[test]
+    fn days_and_times() {
+        assert_eq!(
+            madrid("tomorrow at 9am"),
+            Ok("2026-03-26T09:00:00+01:00".into())
+        );
+        assert_eq!(
+            madrid("today 14:30"),
+            Ok("2026-03-25T14:30:00+01:00".into())
+        );
+        assert_eq!(madrid("tomorrow"), Ok("2026-03-26T09:00:00+01:00".into()));
+        assert_eq!(
+            madrid("tomorrow 12 am"),
+            Ok("2026-03-26T00:00:00+01:00".into())
+        );
+        assert_eq!(madrid("noon"), Ok("2026-03-25T12:00:00+01:00".into()));
+        assert_eq!(madrid("midnight"), Ok("2026-03-26T00:00:00+01:00".into()));
+        // A time that already passed today is tomorrow, unless today was asked for
+        assert_eq!(madrid("8am"), Ok("2026-03-26T08:00:00+01:00".into()));
+        assert_eq!(madrid("today at 8am"), Err("that time has already passed"));
+        assert_eq!(madrid("13pm"), Err("could not understand the time"));
+        assert_eq!(madrid("9:5"), Err("could not understand the time"));
+    }
+
+    #[test]
+    fn weekdays() {
+        assert_eq!(madrid("friday"), Ok("2026-03-27T09:00:00+01:00".into()));
+        assert_eq!(
+            madrid("next fri 5pm"),
+            Ok("2026-03-27T17:00:00+01:00".into())
+        );
+        // Today's weekday is today while the time is ahead, "next" always skips a week
+        assert_eq!(
+            madrid("wednesday 6pm"),
+            Ok("2026-03-25T18:00:00+01:00".into())
+        );
+        assert_eq!(madrid("wed 8am"), Ok("2026-04-01T08:00:00+02:00".into()));
+        assert_eq!(
+            madrid("next wednesday 6pm"),
+            Ok("2026-04-01T18:00:00+02:00".into())
+        );
+        assert_eq!(madrid("next week"), Err("expected a weekday"));
+    }
+
+    #[test]
+    fn absolute() {
+        assert_eq!(
+            madrid("2026-12-01 14:00"),
+            Ok("2026-12-01T14:00:00+01:00".into())
+        );
+        assert_eq!(madrid("dec 1 2pm"), Ok("2026-12-01T14:00:00+01:00".into()));
+        assert_eq!(
+            madrid("1 december at 2 pm"),
+            Ok("2026-12-01T14:00:00+01:00".into())
+        );
+        assert_eq!(
+            madrid("december 1st, 2027"),
+            Ok("2027-12-01T09:00:00+01:00".into())
+        );
+        // Dates without a year that already passed this year are next year's
+        assert_eq!(madrid("jan 5"), Ok("2027-01-05T09:00:00+01:00".into()));
+        assert_eq!(madrid("2025-12-01"), Err("that time has already passed"));
+        assert_eq!(madrid("feb 30"), Err("no such date"));
+    }
+
+    #[test]
+    fn ambiguous() {
+        assert!(madrid("12/01").is_err());
+        assert_eq!(madrid("tomorrow friday"), Err("more than one date"));
+        assert_eq!(madrid("9am 10am"), Err("more than one time"));
+        assert_eq!(madrid("at"), Err("no time given"));
+        assert_eq!(madrid(""), Err("no time given"));
+        assert_eq!(madrid("whenever"), Err("could not understand the time"));
+    }
+
+    #[test]
+    fn dst_edges() {
+        // Madrid skips 02:00-03:00 on 2026-03-29, the missing time moves forward
+        assert_eq!(
+            madrid("mar 29 2:30"),
+            Ok("2026-03-29T03:30:00+02:00".into())
+        );
+        // Local times keep their wall clock time across the change
+        assert_eq!(madrid("sunday 9am"), Ok("2026-03-29T09:00:00+02:00".into()));
+        // While relative times are exact durations
+        assert_eq!(
+            parse("in 4 days", Madrid, now()),
+            Ok(utc("2026-03-29T09:00:00Z"))
+        );
+        // 02:30 happens twice on 2026-10-25, the first one is used
+        assert_eq!(
+            madrid("oct 25 2:30"),
+            Ok("2026-10-25T02:30:00+02:00".into())
+        );
+        // Other timezones change on other dates
+        assert_eq!(
+            parse("mar 14 2027 2:30am", New_York, now()),
+            Ok(utc("2027-03-14T07:30:00Z"))
+        );
+    }
+}